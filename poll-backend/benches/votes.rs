@@ -0,0 +1,203 @@
+//! Creating polls and answering them as the number of slots grows, through
+//! the HTTP API like the frontend does. Runs on a SQLite file in the temp
+//! directory, or on `BENCH_DATABASE_URL` (a scratch database; the polls are
+//! left behind).
+//!
+//! `cargo bench --bench votes`
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use metrics_exporter_prometheus::PrometheusBuilder;
+use serde_json::{json, Value};
+use tokio::{net::TcpListener, runtime::Runtime};
+
+use poll_backend::{
+    app,
+    client_ip::TrustedProxies,
+    clock::SystemClock,
+    config::{AllowedOrigins, DatabaseConfig, HttpConfig},
+    db,
+    password_reset::LogResetLinkSender,
+    policy::{AuthPolicy, PasswordPolicy, SignupMode},
+    repository::SqlEventRepository,
+    revision::PollCache,
+    state::AppState,
+    webhooks::AllowedNetworks,
+};
+
+const SLOT_COUNTS: [usize; 3] = [10, 100, 500];
+
+struct Server {
+    base: String,
+    client: reqwest::Client,
+    token: String,
+}
+
+impl Server {
+    async fn start(url: String) -> Self {
+        let pool = db::connect(&DatabaseConfig {
+            url,
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(10),
+            idle_timeout: Duration::from_secs(60),
+        })
+        .await
+        .unwrap();
+        db::migrate(&pool).await.unwrap();
+
+        let state = AppState {
+            pool: pool.clone(),
+            events: Arc::new(SqlEventRepository::new(pool)),
+            reset_sender: Arc::new(LogResetLinkSender),
+            auth_policy: Arc::new(AuthPolicy {
+                signup_mode: SignupMode::Open,
+                password: PasswordPolicy::default(),
+                secure_cookies: false,
+            }),
+            public_base_url: "http://localhost:5173".to_string(),
+            clock: Arc::new(SystemClock),
+            oidc: None,
+            trusted_proxies: Arc::new(TrustedProxies(Vec::new())),
+            webhook_networks: Arc::new(AllowedNetworks::default()),
+            poll_cache: Arc::new(PollCache::new(1000)),
+            metrics: PrometheusBuilder::new().build_recorder().handle(),
+        };
+        let http = HttpConfig {
+            allowed_origins: AllowedOrigins::List(Vec::new()),
+            body_limit: 2 * 1024 * 1024,
+            trusted_proxies: Vec::new(),
+            legacy_api_sunset: None,
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/api/v1", listener.local_addr().unwrap());
+        let app = app(state, &http);
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+
+        let client = reqwest::Client::new();
+        let name = format!("bench-{}", uuid::Uuid::new_v4().simple());
+        let signup: Value = client
+            .post(format!("{base}/admin/signup"))
+            .json(&json!({ "name": name, "password": "correct horse battery" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let token = signup["token"].as_str().unwrap().to_string();
+
+        Self {
+            base,
+            client,
+            token,
+        }
+    }
+
+    async fn create_poll(&self, slots: usize) -> String {
+        let time_slots: Vec<_> = (0..slots)
+            .map(|i| {
+                let starts_at = chrono::DateTime::UNIX_EPOCH + chrono::Duration::hours(i as i64);
+                json!({
+                    "starts_at": starts_at,
+                    "ends_at": starts_at + chrono::Duration::hours(1),
+                })
+            })
+            .collect();
+        let response = self
+            .client
+            .post(format!("{}/poll", self.base))
+            .header("x-admin-token", &self.token)
+            .json(&json!({ "title": "Offsite", "time_slots": time_slots }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+        let body: Value = response.json().await.unwrap();
+        body["id"].as_str().unwrap().to_string()
+    }
+
+    async fn slot_ids(&self, poll_id: &str) -> Vec<String> {
+        let poll: Value = self
+            .client
+            .get(format!("{}/poll/{poll_id}", self.base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        poll["time_slots"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|slot| slot["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    async fn vote(&self, poll_id: &str, answers: &Value) {
+        let response = self
+            .client
+            .post(format!("{}/poll/{poll_id}/vote", self.base))
+            .json(answers)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+    }
+}
+
+fn votes(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let sqlite_file =
+        std::env::temp_dir().join(format!("poll_bench_{}.db", uuid::Uuid::new_v4().simple()));
+    let url = std::env::var("BENCH_DATABASE_URL")
+        .unwrap_or_else(|_| format!("sqlite://{}", sqlite_file.display()));
+    let server = runtime.block_on(Server::start(url));
+
+    let mut group = c.benchmark_group("create_poll");
+    for slots in SLOT_COUNTS {
+        group.throughput(Throughput::Elements(slots as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(slots), &slots, |b, &slots| {
+            b.to_async(&runtime).iter(|| server.create_poll(slots));
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("submit_vote");
+    for slots in SLOT_COUNTS {
+        let poll_id = runtime.block_on(server.create_poll(slots));
+        let answers: Vec<_> = runtime
+            .block_on(server.slot_ids(&poll_id))
+            .into_iter()
+            .enumerate()
+            .map(|(i, id)| json!({ "time_slot_id": id, "available": i % 2 == 0 }))
+            .collect();
+        let request = json!({ "participant_name": "Bench", "votes": answers });
+
+        group.throughput(Throughput::Elements(slots as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(slots), &slots, |b, _| {
+            b.to_async(&runtime)
+                .iter(|| server.vote(&poll_id, &request));
+        });
+    }
+    group.finish();
+
+    for suffix in ["", "-wal", "-shm"] {
+        let mut file = sqlite_file.clone().into_os_string();
+        file.push(suffix);
+        std::fs::remove_file(file).ok();
+    }
+}
+
+criterion_group!(benches, votes);
+criterion_main!(benches);
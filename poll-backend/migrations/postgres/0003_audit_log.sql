@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS audit_log (
+    id         BIGSERIAL PRIMARY KEY,
+    event_id   TEXT,
+    admin_id   TEXT,
+    action     TEXT NOT NULL,
+    details    TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_event_id ON audit_log(event_id, id);
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS poll_templates (
+    id          TEXT PRIMARY KEY,
+    admin_id    TEXT NOT NULL REFERENCES admins(id) ON DELETE CASCADE,
+    name        TEXT NOT NULL,
+    title       TEXT NOT NULL,
+    description TEXT,
+    created_at  TEXT NOT NULL,
+    UNIQUE (admin_id, name)
+);
+
+-- Slots are stored relative to the template's first slot so they can be
+-- placed at any date when a poll is created from the template.
+CREATE TABLE IF NOT EXISTS poll_template_slots (
+    id                   TEXT PRIMARY KEY,
+    template_id          TEXT NOT NULL REFERENCES poll_templates(id) ON DELETE CASCADE,
+    start_offset_minutes BIGINT NOT NULL,
+    duration_minutes     BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_poll_template_slots_template_id ON poll_template_slots(template_id);
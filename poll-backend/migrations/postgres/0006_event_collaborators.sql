@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS event_collaborators (
+    event_id   TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    admin_id   TEXT NOT NULL REFERENCES admins(id) ON DELETE CASCADE,
+    role       TEXT NOT NULL CHECK (role IN ('owner', 'editor', 'viewer')),
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (event_id, admin_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_collaborators_admin_id ON event_collaborators(admin_id);
+
+-- Every existing event is owned by the admin who created it
+INSERT INTO event_collaborators (event_id, admin_id, role, created_at)
+SELECT e.id, e.admin_id, 'owner', e.created_at
+FROM events e
+JOIN admins a ON a.id = e.admin_id
+ON CONFLICT (event_id, admin_id) DO NOTHING;
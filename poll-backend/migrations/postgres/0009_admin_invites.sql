@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS admin_invites (
+    code_hash  TEXT PRIMARY KEY,
+    created_by TEXT REFERENCES admins(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    used_at    TEXT,
+    used_by    TEXT
+);
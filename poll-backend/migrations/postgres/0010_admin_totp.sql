@@ -0,0 +1,27 @@
+-- The secret has to stay readable to check codes, so it is stored as is.
+-- enabled_at stays NULL until the admin confirms a first code.
+CREATE TABLE IF NOT EXISTS admin_totp (
+    admin_id       TEXT PRIMARY KEY REFERENCES admins(id) ON DELETE CASCADE,
+    secret         TEXT NOT NULL,
+    created_at     TEXT NOT NULL,
+    enabled_at     TEXT,
+    last_used_step BIGINT
+);
+
+CREATE TABLE IF NOT EXISTS admin_recovery_codes (
+    code_hash TEXT PRIMARY KEY,
+    admin_id  TEXT NOT NULL REFERENCES admins(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL,
+    used_at   TEXT
+);
+
+CREATE INDEX IF NOT EXISTS admin_recovery_codes_admin_id_idx ON admin_recovery_codes (admin_id);
+
+-- Issued after a correct password when a second factor is still needed
+CREATE TABLE IF NOT EXISTS admin_login_challenges (
+    token_hash TEXT PRIMARY KEY,
+    admin_id   TEXT NOT NULL REFERENCES admins(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    attempts   INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,3 @@
+-- Per-session secret that cookie-authenticated requests echo in a header.
+-- Sessions from before this stay usable with the x-admin-token header only.
+ALTER TABLE admin_sessions ADD COLUMN IF NOT EXISTS csrf_token TEXT;
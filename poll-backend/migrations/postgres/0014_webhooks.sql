@@ -0,0 +1,34 @@
+CREATE TABLE IF NOT EXISTS webhooks (
+    id          TEXT PRIMARY KEY,
+    admin_id    TEXT NOT NULL REFERENCES admins(id) ON DELETE CASCADE,
+    -- NULL covers every event the admin owns
+    event_id    TEXT REFERENCES events(id) ON DELETE CASCADE,
+    url         TEXT NOT NULL,
+    -- Signs every payload, so it is kept in the clear
+    secret      TEXT NOT NULL,
+    -- Space-separated, e.g. "participant.created votes.updated"
+    event_types TEXT NOT NULL,
+    created_at  TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhooks_admin_id ON webhooks(admin_id);
+CREATE INDEX IF NOT EXISTS idx_webhooks_event_id ON webhooks(event_id);
+
+-- Both the retry queue and the delivery log: rows stay 'pending' until they
+-- are delivered or run out of attempts.
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id              TEXT PRIMARY KEY,
+    webhook_id      TEXT NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    event_type      TEXT NOT NULL,
+    payload         TEXT NOT NULL,
+    status          TEXT NOT NULL CHECK (status IN ('pending', 'delivered', 'failed')),
+    attempts        INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL,
+    last_attempt_at TEXT,
+    response_status INTEGER,
+    last_error      TEXT,
+    created_at      TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_status ON webhook_deliveries(status, next_attempt_at);
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook_id ON webhook_deliveries(webhook_id, created_at);
@@ -0,0 +1,6 @@
+-- Bumped by every write that changes what the poll page shows; the API sends
+-- it as the poll's ETag
+ALTER TABLE events ADD COLUMN IF NOT EXISTS revision BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE events ADD COLUMN IF NOT EXISTS updated_at TEXT;
+
+UPDATE events SET updated_at = created_at WHERE updated_at IS NULL;
@@ -0,0 +1,4 @@
+-- Wrong second-factor codes in a row, across login challenges, and until when
+-- the second login step is locked after too many
+ALTER TABLE admin_totp ADD COLUMN IF NOT EXISTS failed_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE admin_totp ADD COLUMN IF NOT EXISTS locked_until TEXT;
@@ -0,0 +1,6 @@
+-- Deleting an organization leaves its polls behind instead of taking them
+-- along, so they can go through the trash like any other deleted poll
+ALTER TABLE events DROP CONSTRAINT IF EXISTS events_organization_id_fkey;
+ALTER TABLE events
+    ADD CONSTRAINT events_organization_id_fkey
+    FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE SET NULL;
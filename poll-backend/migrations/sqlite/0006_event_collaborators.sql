@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS event_collaborators (
+    event_id   TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    admin_id   TEXT NOT NULL REFERENCES admins(id) ON DELETE CASCADE,
+    role       TEXT NOT NULL CHECK (role IN ('owner', 'editor', 'viewer')),
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (event_id, admin_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_collaborators_admin_id ON event_collaborators(admin_id);
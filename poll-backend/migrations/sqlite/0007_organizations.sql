@@ -0,0 +1,44 @@
+CREATE TABLE IF NOT EXISTS organizations (
+    id         TEXT PRIMARY KEY,
+    name       TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS organization_members (
+    organization_id TEXT NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    admin_id        TEXT NOT NULL REFERENCES admins(id) ON DELETE CASCADE,
+    role            TEXT NOT NULL CHECK (role IN ('owner', 'admin', 'member')),
+    created_at      TEXT NOT NULL,
+    PRIMARY KEY (organization_id, admin_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_organization_members_admin_id ON organization_members(admin_id);
+
+ALTER TABLE events
+    ADD COLUMN organization_id TEXT REFERENCES organizations(id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_events_organization_id ON events(organization_id);
+
+-- Effective role of each admin on each event: the strongest of a direct
+-- collaborator grant and the role implied by membership of the owning
+-- organization (owners and admins manage every team poll, members view them).
+CREATE VIEW IF NOT EXISTS event_access AS
+SELECT
+    event_id,
+    admin_id,
+    CASE MAX(rank) WHEN 3 THEN 'owner' WHEN 2 THEN 'editor' ELSE 'viewer' END AS role
+FROM (
+    SELECT
+        event_id,
+        admin_id,
+        CASE role WHEN 'owner' THEN 3 WHEN 'editor' THEN 2 ELSE 1 END AS rank
+    FROM event_collaborators
+    UNION ALL
+    SELECT
+        e.id AS event_id,
+        m.admin_id,
+        CASE m.role WHEN 'member' THEN 1 ELSE 3 END AS rank
+    FROM events e
+    JOIN organization_members m ON m.organization_id = e.organization_id
+) grants
+GROUP BY event_id, admin_id;
@@ -0,0 +1,21 @@
+-- External identities that can sign in as an admin
+CREATE TABLE IF NOT EXISTS admin_identities (
+    issuer     TEXT NOT NULL,
+    subject    TEXT NOT NULL,
+    admin_id   TEXT NOT NULL REFERENCES admins(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (issuer, subject)
+);
+
+CREATE INDEX IF NOT EXISTS admin_identities_admin_id_idx ON admin_identities (admin_id);
+
+-- In-flight logins, between the redirect to the provider and its callback.
+-- link_admin_id is set when a signed-in admin is linking an identity.
+CREATE TABLE IF NOT EXISTS oidc_login_states (
+    state_hash    TEXT PRIMARY KEY,
+    code_verifier TEXT NOT NULL,
+    nonce         TEXT NOT NULL,
+    link_admin_id TEXT REFERENCES admins(id) ON DELETE CASCADE,
+    created_at    TEXT NOT NULL,
+    expires_at    TEXT NOT NULL
+);
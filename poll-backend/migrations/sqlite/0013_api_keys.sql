@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS api_keys (
+    id           TEXT PRIMARY KEY,
+    admin_id     TEXT NOT NULL REFERENCES admins(id) ON DELETE CASCADE,
+    name         TEXT NOT NULL,
+    key_prefix   TEXT NOT NULL,
+    key_hash     TEXT NOT NULL UNIQUE,
+    -- Space-separated, e.g. "events:read polls:create"
+    scopes       TEXT NOT NULL,
+    created_at   TEXT NOT NULL,
+    expires_at   TEXT,
+    last_used_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS api_keys_admin_id_idx ON api_keys (admin_id);
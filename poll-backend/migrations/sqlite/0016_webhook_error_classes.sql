@@ -0,0 +1,4 @@
+-- Failed deliveries used to keep the start of the receiver's answer, which
+-- could hand an admin content from internal services. Only error classes are
+-- stored now
+UPDATE webhook_deliveries SET last_error = NULL WHERE response_status IS NOT NULL;
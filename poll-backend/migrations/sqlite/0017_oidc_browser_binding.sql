@@ -0,0 +1,4 @@
+-- Hash of the value in the browser's oidc_binding cookie; a callback only
+-- completes in the browser that started the login. Logins already in flight
+-- have none and fail, but they expire within minutes anyway
+ALTER TABLE oidc_login_states ADD COLUMN browser_hash TEXT;
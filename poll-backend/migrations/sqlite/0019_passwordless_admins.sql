@@ -0,0 +1,3 @@
+-- Admins signed up through the OpenID Connect provider have a random password
+-- nobody knows until they set one
+ALTER TABLE admins ADD COLUMN has_password INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,10 @@
+-- Deleting an organization leaves its polls behind instead of taking them
+-- along, so they can go through the trash like any other deleted poll.
+-- SQLite can't change a foreign key without rebuilding the table, which would
+-- cascade to everything that references events; clearing the reference first
+-- leaves the ON DELETE CASCADE nothing to do.
+CREATE TRIGGER IF NOT EXISTS organizations_keep_events
+BEFORE DELETE ON organizations
+BEGIN
+    UPDATE events SET organization_id = NULL WHERE organization_id = OLD.id;
+END;
@@ -0,0 +1,118 @@
+//! API versions. Routes are served under `/api/v1`; the unversioned `/api`
+//! paths from before versioning still answer the same way, but announce their
+//! retirement with `Deprecation` (RFC 9745), `Sunset` (RFC 8594) and a `Link`
+//! to the versioned path. A later version retires v1 the same way.
+
+use axum::{
+    extract::{OriginalUri, Request, State},
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, TimeZone, Utc};
+
+/// Prefix of the current version.
+pub const CURRENT_PREFIX: &str = "/api/v1";
+/// Prefix of the unversioned paths, an alias of v1.
+pub const LEGACY_PREFIX: &str = "/api";
+
+pub const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+pub const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+
+/// When the unversioned paths were deprecated: the day `/api/v1` appeared.
+const LEGACY_DEPRECATED_AT: i64 = 1_792_368_000; // 2026-10-19T00:00:00Z
+
+/// Retirement plan of one API version.
+#[derive(Debug, Clone)]
+pub struct Deprecation {
+    /// Prefix the version is served under
+    pub prefix: &'static str,
+    /// Prefix of the version replacing it
+    pub successor_prefix: &'static str,
+    pub deprecated_at: DateTime<Utc>,
+    /// When the version stops answering, once that's decided
+    pub sunset: Option<DateTime<Utc>>,
+}
+
+impl Deprecation {
+    /// The unversioned paths, replaced by v1.
+    pub fn legacy(sunset: Option<DateTime<Utc>>) -> Self {
+        Self {
+            prefix: LEGACY_PREFIX,
+            successor_prefix: CURRENT_PREFIX,
+            deprecated_at: Utc.timestamp_opt(LEGACY_DEPRECATED_AT, 0).unwrap(),
+            sunset,
+        }
+    }
+
+    /// Same request against the successor version.
+    fn successor(&self, path_and_query: &str) -> Option<String> {
+        path_and_query
+            .strip_prefix(self.prefix)
+            .map(|rest| format!("{}{rest}", self.successor_prefix))
+    }
+}
+
+/// Middleware for the routes of a deprecated version.
+pub async fn mark_deprecated(
+    State(deprecation): State<Deprecation>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Nested routers see the path without their prefix
+    let successor = request
+        .extensions()
+        .get::<OriginalUri>()
+        .and_then(|OriginalUri(uri)| uri.path_and_query())
+        .and_then(|path| deprecation.successor(path.as_str()));
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+
+    let deprecated_at = format!("@{}", deprecation.deprecated_at.timestamp());
+    if let Ok(value) = HeaderValue::from_str(&deprecated_at) {
+        headers.insert(DEPRECATION_HEADER, value);
+    }
+    if let Some(sunset) = deprecation.sunset {
+        if let Ok(value) = HeaderValue::from_str(&http_date(sunset)) {
+            headers.insert(SUNSET_HEADER, value);
+        }
+    }
+    if let Some(successor) = successor {
+        let link = format!("<{successor}>; rel=\"successor-version\"");
+        if let Ok(value) = HeaderValue::from_str(&link) {
+            headers.append(header::LINK, value);
+        }
+    }
+    response
+}
+
+/// IMF-fixdate, as HTTP headers want dates.
+pub(crate) fn http_date(at: DateTime<Utc>) -> String {
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_to_the_same_request_in_v1() {
+        let legacy = Deprecation::legacy(None);
+        assert_eq!(
+            legacy.successor("/api/events?limit=5").as_deref(),
+            Some("/api/v1/events?limit=5")
+        );
+        assert_eq!(legacy.successor("/healthz"), None);
+    }
+
+    #[test]
+    fn formats_http_dates() {
+        let sunset = Utc.with_ymd_and_hms(2027, 6, 30, 0, 0, 0).unwrap();
+        assert_eq!(http_date(sunset), "Wed, 30 Jun 2027 00:00:00 GMT");
+        assert_eq!(
+            Deprecation::legacy(None).deprecated_at,
+            Utc.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use serde_json::Value;
+use sqlx::AnyExecutor;
+
+// Audit entries are append-only and deliberately carry no foreign keys, so the
+// history of an event outlives the event itself.
+pub async fn record<'e>(
+    executor: impl AnyExecutor<'e>,
+    event_id: Option<&str>,
+    admin_id: Option<&str>,
+    action: &str,
+    details: Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO audit_log (event_id, admin_id, action, details, created_at) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(event_id)
+    .bind(admin_id)
+    .bind(action)
+    .bind(details.to_string())
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
@@ -1,45 +1,461 @@
-use axum::http::HeaderMap;
-use sqlx::PgPool;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::header::{HeaderName, AUTHORIZATION, COOKIE, SET_COOKIE};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, Method};
+use axum::response::AppendHeaders;
+use cookie::{Cookie, SameSite};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use sqlx::AnyPool;
+use std::marker::PhantomData;
+use subtle::ConstantTimeEq;
 
 use axum::http::StatusCode;
 
+use crate::db::Backend;
+use crate::models::{ApiScope, EventRole, OrgRole};
+
+/// HttpOnly cookie carrying the session token for browsers.
+pub const SESSION_COOKIE: &str = "admin_session";
+/// Readable by the frontend, which echoes it in `CSRF_HEADER`.
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+/// Header for API clients that don't keep cookies. `Authorization: Bearer` works too.
+pub const TOKEN_HEADER: &str = "x-admin-token";
+/// Marks API keys, so they can share the headers session tokens use.
+pub const API_KEY_PREFIX: &str = "gtk_";
+const SESSION_COOKIE_MAX_AGE_DAYS: i64 = 30;
+
+pub type SessionCookies = AppendHeaders<[(HeaderName, String); 2]>;
+
 #[derive(sqlx::FromRow)]
 struct AdminContextRow {
     admin_id: String,
+    csrf_token: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ApiKeyRow {
+    id: String,
+    admin_id: String,
+    scopes: String,
 }
 
+/// The signed-in admin. Taking it as a handler argument makes the route
+/// answer 401 without a valid session.
+///
+/// The session token comes from the `x-admin-token` header, an
+/// `Authorization: Bearer` header or, for browsers, the session cookie.
+/// Cookie-authenticated requests that change state must also send the
+/// session's CSRF token in `x-csrf-token`. API keys are refused here with 403;
+/// routes open to them take [`Scoped`] instead.
 pub struct AdminContext {
     pub admin_id: String,
+    /// The session token, or the key id when authenticated by API key
     pub token: String,
 }
 
-pub async fn require_admin(
-    pool: &PgPool,
-    headers: &HeaderMap,
-) -> Result<AdminContext, StatusCode> {
-    let token = headers
-        .get("x-admin-token")
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminContext
+where
+    AnyPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let pool = AnyPool::from_ref(state);
+        authenticate(&pool, &parts.method, &parts.headers, None).await
+    }
+}
+
+/// Names the [`ApiScope`] a [`Scoped`] route needs.
+pub trait RequiredScope {
+    const SCOPE: ApiScope;
+}
+
+pub struct ReadEvents;
+pub struct CreatePolls;
+pub struct ExportPolls;
+
+impl RequiredScope for ReadEvents {
+    const SCOPE: ApiScope = ApiScope::ReadEvents;
+}
+
+impl RequiredScope for CreatePolls {
+    const SCOPE: ApiScope = ApiScope::CreatePolls;
+}
+
+impl RequiredScope for ExportPolls {
+    const SCOPE: ApiScope = ApiScope::ExportPolls;
+}
+
+/// Like [`AdminContext`], but also accepts API keys that carry the scope `S`.
+/// Handlers usually destructure it: `Scoped { admin, .. }: Scoped<ReadEvents>`.
+pub struct Scoped<S> {
+    pub admin: AdminContext,
+    scope: PhantomData<S>,
+}
+
+#[async_trait]
+impl<S, R> FromRequestParts<S> for Scoped<R>
+where
+    AnyPool: FromRef<S>,
+    S: Send + Sync,
+    R: RequiredScope,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let pool = AnyPool::from_ref(state);
+        let admin = authenticate(&pool, &parts.method, &parts.headers, Some(R::SCOPE)).await?;
+        Ok(Scoped {
+            admin,
+            scope: PhantomData,
+        })
+    }
+}
+
+/// A newly issued session.
+pub struct Session {
+    pub token: String,
+    pub csrf_token: String,
+}
+
+/// One-time secrets such as reset tokens and invite codes are stored hashed,
+/// so a leaked table can't be used to take over accounts.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub fn hash_password(password: &str) -> Result<String, StatusCode> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Responds 401 when `password` does not match the stored hash.
+pub fn verify_password(password: &str, password_hash: &str) -> Result<(), StatusCode> {
+    let parsed_hash =
+        PasswordHash::new(password_hash).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .map_err(|_| StatusCode::UNAUTHORIZED)
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Starts a new session for `admin_id`.
+pub async fn create_session<'e>(
+    executor: impl sqlx::AnyExecutor<'e>,
+    admin_id: &str,
+) -> Result<Session, sqlx::Error> {
+    let session = Session {
+        token: uuid::Uuid::new_v4().to_string(),
+        csrf_token: random_token(),
+    };
+
+    sqlx::query(
+        "INSERT INTO admin_sessions (id, admin_id, created_at, csrf_token) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(&session.token)
+    .bind(admin_id)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .bind(&session.csrf_token)
+    .execute(executor)
+    .await?;
+
+    Ok(session)
+}
+
+fn build_cookie(name: &'static str, value: String, http_only: bool, secure: bool) -> Cookie<'static> {
+    Cookie::build((name, value))
+        .path("/")
+        .http_only(http_only)
+        .secure(secure)
+        .same_site(SameSite::Lax)
+        .max_age(cookie::time::Duration::days(SESSION_COOKIE_MAX_AGE_DAYS))
+        .build()
+}
+
+/// `Set-Cookie` headers that sign a browser in with `session`.
+pub fn session_cookies(session: &Session, secure: bool) -> SessionCookies {
+    AppendHeaders([
+        (
+            SET_COOKIE,
+            build_cookie(SESSION_COOKIE, session.token.clone(), true, secure).to_string(),
+        ),
+        (
+            SET_COOKIE,
+            build_cookie(CSRF_COOKIE, session.csrf_token.clone(), false, secure).to_string(),
+        ),
+    ])
+}
+
+/// `Set-Cookie` headers that remove the session cookies again.
+pub fn clear_session_cookies(secure: bool) -> SessionCookies {
+    let expired = |name, http_only| {
+        let mut cookie = build_cookie(name, String::new(), http_only, secure);
+        cookie.make_removal();
+        cookie.to_string()
+    };
+    AppendHeaders([
+        (SET_COOKIE, expired(SESSION_COOKIE, true)),
+        (SET_COOKIE, expired(CSRF_COOKIE, false)),
+    ])
+}
+
+fn header_value<'h>(headers: &'h HeaderMap, name: &str) -> Option<&'h str> {
+    headers
+        .get(name)
         .and_then(|value| value.to_str().ok())
-        .map(|value| value.trim())
+        .map(str::trim)
         .filter(|value| !value.is_empty())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+}
+
+pub(crate) fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(Cookie::split_parse)
+        .filter_map(Result::ok)
+        .find(|cookie| cookie.name() == name)
+        .map(|cookie| cookie.value().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    header_value(headers, AUTHORIZATION.as_str())?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+async fn authenticate(
+    pool: &AnyPool,
+    method: &Method,
+    headers: &HeaderMap,
+    scope: Option<ApiScope>,
+) -> Result<AdminContext, StatusCode> {
+    // A header can't be attached by another site, so only cookies need CSRF checks
+    let (token, from_cookie) = match header_value(headers, TOKEN_HEADER).or(bearer_token(headers)) {
+        Some(token) => (token.to_string(), false),
+        None => (
+            cookie_value(headers, SESSION_COOKIE).ok_or(StatusCode::UNAUTHORIZED)?,
+            true,
+        ),
+    };
+
+    if token.starts_with(API_KEY_PREFIX) && !from_cookie {
+        return authenticate_api_key(pool, &token, scope).await;
+    }
 
     let admin = sqlx::query_as::<_, AdminContextRow>(
         r#"
-        SELECT a.id AS admin_id
+        SELECT a.id AS admin_id, s.csrf_token
         FROM admin_sessions s
         JOIN admins a ON a.id = s.admin_id
         WHERE s.id = $1
         "#,
     )
-    .bind(token)
+    .bind(&token)
     .fetch_optional(pool)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .ok_or(StatusCode::UNAUTHORIZED)?;
 
+    let safe_method = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+    if from_cookie && !safe_method {
+        let expected = admin.csrf_token.as_deref().ok_or(StatusCode::FORBIDDEN)?;
+        let sent = header_value(headers, CSRF_HEADER).ok_or(StatusCode::FORBIDDEN)?;
+        if !bool::from(sent.as_bytes().ct_eq(expected.as_bytes())) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
     Ok(AdminContext {
         admin_id: admin.admin_id,
-        token: token.to_string(),
+        token,
     })
 }
+
+/// Checks an API key and records its use. Responds 401 for unknown or expired
+/// keys and 403 when the route takes no keys or the key lacks its scope.
+async fn authenticate_api_key(
+    pool: &AnyPool,
+    key: &str,
+    scope: Option<ApiScope>,
+) -> Result<AdminContext, StatusCode> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let backend = Backend::of_pool(pool);
+
+    let sql = format!(
+        r#"
+        UPDATE api_keys
+        SET last_used_at = $2
+        WHERE key_hash = $1
+          AND (expires_at IS NULL OR {} > {})
+        RETURNING id, admin_id, scopes
+        "#,
+        backend.timestamp("expires_at"),
+        backend.timestamp("$2"),
+    );
+    let row = sqlx::query_as::<_, ApiKeyRow>(&sql)
+        .bind(hash_token(key))
+        .bind(&now)
+        .fetch_optional(pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let scope = scope.ok_or(StatusCode::FORBIDDEN)?;
+    if !ApiScope::parse_list(&row.scopes).contains(&scope) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(AdminContext {
+        admin_id: row.admin_id,
+        token: row.id,
+    })
+}
+
+/// Checks that `admin` holds at least `required` on a live (not soft-deleted)
+/// event, either as a collaborator or through the organization owning it.
+/// Responds 404 when the event does not exist and 403 when the admin has no
+/// access or their role is too low.
+pub async fn require_event_role(
+    pool: &AnyPool,
+    admin: &AdminContext,
+    event_id: &str,
+    required: EventRole,
+) -> Result<EventRole, StatusCode> {
+    check_event_role(pool, admin, event_id, required, false).await
+}
+
+/// Same as [`require_event_role`], for soft-deleted events awaiting purge.
+pub async fn require_deleted_event_role(
+    pool: &AnyPool,
+    admin: &AdminContext,
+    event_id: &str,
+    required: EventRole,
+) -> Result<EventRole, StatusCode> {
+    check_event_role(pool, admin, event_id, required, true).await
+}
+
+async fn check_event_role(
+    pool: &AnyPool,
+    admin: &AdminContext,
+    event_id: &str,
+    required: EventRole,
+    deleted: bool,
+) -> Result<EventRole, StatusCode> {
+    let membership: Option<Option<EventRole>> = sqlx::query_scalar(
+        r#"
+        SELECT a.role
+        FROM events e
+        LEFT JOIN event_access a ON a.event_id = e.id AND a.admin_id = $2
+        WHERE e.id = $1 AND (e.deleted_at IS NOT NULL) = $3
+        "#,
+    )
+    .bind(event_id)
+    .bind(&admin.admin_id)
+    .bind(deleted)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to check event role");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let Some(role) = membership else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    match role {
+        Some(role) if role >= required => Ok(role),
+        _ => Err(StatusCode::FORBIDDEN),
+    }
+}
+
+/// Checks that `admin` belongs to the organization with at least `required`.
+/// Responds 404 when the organization does not exist and 403 otherwise.
+pub async fn require_org_role(
+    pool: &AnyPool,
+    admin: &AdminContext,
+    organization_id: &str,
+    required: OrgRole,
+) -> Result<OrgRole, StatusCode> {
+    let membership: Option<Option<OrgRole>> = sqlx::query_scalar(
+        r#"
+        SELECT m.role
+        FROM organizations o
+        LEFT JOIN organization_members m ON m.organization_id = o.id AND m.admin_id = $2
+        WHERE o.id = $1
+        "#,
+    )
+    .bind(organization_id)
+    .bind(&admin.admin_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(
+            error = ?e,
+            organization_id = %organization_id,
+            "Failed to check organization role"
+        );
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let Some(role) = membership else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    match role {
+        Some(role) if role >= required => Ok(role),
+        _ => Err(StatusCode::FORBIDDEN),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn session_cookie_is_http_only_and_csrf_cookie_is_readable() {
+        let session = Session {
+            token: "t".into(),
+            csrf_token: "c".into(),
+        };
+        let AppendHeaders([(_, session_cookie), (_, csrf_cookie)]) = session_cookies(&session, true);
+
+        assert!(session_cookie.starts_with("admin_session=t;"));
+        assert!(session_cookie.contains("HttpOnly"));
+        assert!(session_cookie.contains("Secure"));
+        assert!(session_cookie.contains("SameSite=Lax"));
+        assert!(csrf_cookie.starts_with("csrf_token=c;"));
+        assert!(!csrf_cookie.contains("HttpOnly"));
+    }
+
+    #[test]
+    fn finds_cookies_among_several_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append(COOKIE, HeaderValue::from_static("theme=dark; csrf_token=c"));
+        headers.append(COOKIE, HeaderValue::from_static("admin_session=t"));
+
+        assert_eq!(cookie_value(&headers, SESSION_COOKIE).as_deref(), Some("t"));
+        assert_eq!(cookie_value(&headers, CSRF_COOKIE).as_deref(), Some("c"));
+        assert_eq!(cookie_value(&headers, "missing"), None);
+    }
+}
@@ -0,0 +1,126 @@
+//! The address a request really came from, looking through trusted reverse
+//! proxies.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{request::Parts, HeaderMap},
+};
+use ipnet::IpNet;
+
+/// Proxies allowed to report the client address in `X-Forwarded-For`.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(pub Vec<IpNet>);
+
+impl TrustedProxies {
+    fn contains(&self, addr: &IpAddr) -> bool {
+        self.0.iter().any(|net| net.contains(addr))
+    }
+
+    /// Walks `X-Forwarded-For` from the nearest hop back while the hops are
+    /// trusted; the first untrusted one is the client. Headers from untrusted
+    /// peers are ignored, since anyone can send them.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.contains(&peer) {
+            return peer;
+        }
+
+        let forwarded: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+
+        let mut client = peer;
+        for hop in forwarded.into_iter().rev() {
+            // Stop at garbage; the last hop we could read is the best guess
+            let Ok(hop) = hop.trim().parse::<IpAddr>() else {
+                break;
+            };
+            client = hop;
+            if !self.contains(&hop) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// Client address for the audit log. `None` when the server runs without
+/// connection info, as in tests.
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    Arc<TrustedProxies>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let proxies = Arc::<TrustedProxies>::from_ref(state);
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+
+        Ok(ClientIp(
+            peer.map(|peer| proxies.client_ip(peer, &parts.headers)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn proxies(nets: &[&str]) -> TrustedProxies {
+        TrustedProxies(nets.iter().map(|net| net.parse().unwrap()).collect())
+    }
+
+    fn forwarded_for(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn ignores_forwarded_for_from_untrusted_peers() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        let headers = forwarded_for("203.0.113.7");
+        assert_eq!(
+            trusted.client_ip(ip("198.51.100.1"), &headers),
+            ip("198.51.100.1")
+        );
+    }
+
+    #[test]
+    fn skips_trusted_hops_from_the_right() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        // The client made up the first entry; the proxies appended the rest
+        let headers = forwarded_for("1.2.3.4, 203.0.113.7, 10.0.0.5");
+        assert_eq!(
+            trusted.client_ip(ip("10.0.0.1"), &headers),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_peer_without_a_header() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        assert_eq!(
+            trusted.client_ip(ip("10.0.0.1"), &HeaderMap::new()),
+            ip("10.0.0.1")
+        );
+    }
+}
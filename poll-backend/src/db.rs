@@ -0,0 +1,181 @@
+//! The database: Postgres, or a single SQLite file for small installs, picked
+//! by the scheme of `DATABASE_URL`. Queries go through sqlx's `Any` driver
+//! and are written to run on both; the few that can't ask [`Backend`] for
+//! their dialect.
+
+use sqlx::{
+    any::{install_default_drivers, AnyPoolOptions},
+    migrate::{MigrateError, Migrator},
+    Any, AnyConnection, AnyPool, Executor, Transaction,
+};
+
+use crate::config::DatabaseConfig;
+
+static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Postgres,
+    Sqlite,
+}
+
+impl Backend {
+    pub fn from_url(url: &str) -> Option<Self> {
+        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            Some(Self::Postgres)
+        } else if url.starts_with("sqlite:") {
+            Some(Self::Sqlite)
+        } else {
+            None
+        }
+    }
+
+    /// The backend a connection talks to.
+    pub fn of(conn: &AnyConnection) -> Self {
+        match conn.backend_name() {
+            "SQLite" => Self::Sqlite,
+            _ => Self::Postgres,
+        }
+    }
+
+    pub fn of_pool(pool: &AnyPool) -> Self {
+        Self::from_url(pool.connect_options().database_url.as_str()).unwrap_or(Self::Postgres)
+    }
+
+    /// Migrations bundled into the binary; readiness checks they've all been
+    /// applied.
+    pub fn migrator(self) -> &'static Migrator {
+        match self {
+            Self::Postgres => &POSTGRES_MIGRATOR,
+            Self::Sqlite => &SQLITE_MIGRATOR,
+        }
+    }
+
+    /// Locks the selected rows until the transaction ends. SQLite has no row
+    /// locks; a writing transaction holds the whole database instead.
+    pub fn for_update(self) -> &'static str {
+        match self {
+            Self::Postgres => "FOR UPDATE",
+            Self::Sqlite => "",
+        }
+    }
+
+    /// Like [`Self::for_update`], passing over rows another transaction
+    /// already holds.
+    pub fn skip_locked(self) -> &'static str {
+        match self {
+            Self::Postgres => "FOR UPDATE SKIP LOCKED",
+            Self::Sqlite => "",
+        }
+    }
+
+    /// `expr`, an RFC 3339 timestamp stored as text, in a form that compares
+    /// as an instant.
+    pub fn timestamp(self, expr: &str) -> String {
+        match self {
+            Self::Postgres => format!("({expr})::timestamptz"),
+            Self::Sqlite => format!("julianday({expr})"),
+        }
+    }
+}
+
+/// Opens the pool described by `config`. SQLite files are created when
+/// missing unless the URL sets a `mode`.
+pub async fn connect(config: &DatabaseConfig) -> anyhow::Result<AnyPool> {
+    install_default_drivers();
+
+    let backend = Backend::from_url(&config.url)
+        .ok_or_else(|| anyhow::anyhow!("unsupported DATABASE_URL scheme"))?;
+    let mut url = config.url.clone();
+    let mut options = AnyPoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .idle_timeout(config.idle_timeout);
+
+    if backend == Backend::Sqlite {
+        if !url.contains("mode=") {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str("mode=rwc");
+        }
+        // Lets readers carry on while a request writes
+        options = options.after_connect(|conn, _| {
+            Box::pin(async move {
+                conn.execute("PRAGMA journal_mode = WAL").await?;
+                Ok(())
+            })
+        });
+    }
+
+    Ok(options.connect(&url).await?)
+}
+
+/// Brings the schema up to date.
+pub async fn migrate(pool: &AnyPool) -> Result<(), MigrateError> {
+    Backend::of_pool(pool).migrator().run(pool).await
+}
+
+/// Starts a transaction that has `table` to itself for writing until it ends,
+/// for checks no row lock covers, such as counting the rows. Other writers
+/// wait their turn rather than fail.
+pub async fn begin_exclusive(
+    pool: &AnyPool,
+    table: &str,
+) -> sqlx::Result<Transaction<'static, Any>> {
+    match Backend::of_pool(pool) {
+        Backend::Postgres => {
+            let mut tx = pool.begin().await?;
+            tx.execute(format!("LOCK TABLE {table} IN SHARE ROW EXCLUSIVE MODE").as_str())
+                .await?;
+            Ok(tx)
+        }
+        // SQLite locks the whole database; `IMMEDIATE` takes the lock now
+        // instead of at the first write, after the check has already run
+        Backend::Sqlite => pool.begin_with("BEGIN IMMEDIATE").await,
+    }
+}
+
+/// A Postgres array literal, for binding a list as one text parameter to be
+/// cast with `::text[]` or `::int[]`; the `Any` driver can't bind arrays.
+pub fn pg_array<T: ToString>(values: impl IntoIterator<Item = T>) -> String {
+    let elements: Vec<String> = values
+        .into_iter()
+        .map(|value| {
+            let value = value.to_string().replace('\\', "\\\\").replace('"', "\\\"");
+            format!("\"{value}\"")
+        })
+        .collect();
+    format!("{{{}}}", elements.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_backend_from_the_url_scheme() {
+        assert_eq!(
+            Backend::from_url("postgres://poll@localhost/poll"),
+            Some(Backend::Postgres)
+        );
+        assert_eq!(
+            Backend::from_url("postgresql://poll@localhost/poll"),
+            Some(Backend::Postgres)
+        );
+        assert_eq!(
+            Backend::from_url("sqlite://polls.db"),
+            Some(Backend::Sqlite)
+        );
+        assert_eq!(Backend::from_url("sqlite:polls.db"), Some(Backend::Sqlite));
+        assert_eq!(Backend::from_url("mysql://localhost/poll"), None);
+    }
+
+    #[test]
+    fn quotes_array_elements() {
+        assert_eq!(pg_array(["a", "b c"]), r#"{"a","b c"}"#);
+        assert_eq!(pg_array([r#"x"},{"y"#]), r#"{"x\"},{\"y"}"#);
+        assert_eq!(pg_array([1, 0]), r#"{"1","0"}"#);
+        assert_eq!(pg_array(Vec::<String>::new()), "{}");
+    }
+}
@@ -0,0 +1,58 @@
+//! The poll backend as a library. `main.rs` reads the configuration and runs
+//! the server and background tasks; tests drive [`app`] in-process.
+
+mod api_version;
+mod audit;
+mod auth;
+pub mod client_ip;
+pub mod clock;
+pub mod config;
+pub mod db;
+pub mod metrics;
+pub mod models;
+pub mod oidc;
+mod openapi;
+pub mod password_reset;
+pub mod policy;
+pub mod purge;
+pub mod repository;
+mod request_id;
+pub mod revision;
+mod routes;
+pub mod shutdown;
+pub mod state;
+pub mod totp;
+mod votes;
+pub mod webhooks;
+
+use axum::{extract::DefaultBodyLimit, middleware, routing::get, Router};
+
+use api_version::Deprecation;
+use config::HttpConfig;
+use routes::health::{healthz, prometheus_metrics, readyz};
+use state::AppState;
+
+/// Every route with its middleware: the versioned API, its deprecated
+/// unversioned alias, the docs and the operator endpoints.
+pub fn app(state: AppState, http: &HttpConfig) -> Router {
+    let api = routes::api();
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(prometheus_metrics))
+        .route("/api/openapi.json", get(openapi::openapi_json))
+        .route("/api/docs", get(openapi::docs))
+        .nest(api_version::CURRENT_PREFIX, api.clone())
+        .nest(
+            api_version::LEGACY_PREFIX,
+            api.layer(middleware::from_fn_with_state(
+                Deprecation::legacy(http.legacy_api_sunset),
+                api_version::mark_deprecated,
+            )),
+        )
+        .layer(DefaultBodyLimit::max(http.body_limit))
+        .layer(middleware::from_fn(metrics::track_requests))
+        .layer(http.cors_layer())
+        .layer(middleware::from_fn(request_id::trace_requests))
+        .with_state(state)
+}
@@ -0,0 +1,119 @@
+//! Prometheus metrics: request counts and latency per route, pool usage and
+//! a few domain counters. Handlers bump the domain counters through the
+//! helpers below, after their transaction commits.
+
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::AnyPool;
+
+const REQUEST_DURATION: &str = "http_request_duration_seconds";
+const REQUESTS: &str = "http_requests_total";
+
+/// Latency buckets in seconds, from a cache hit to a slow transaction.
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Installs the global recorder. Call once, before serving.
+pub fn install() -> anyhow::Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(REQUEST_DURATION.to_string()),
+            DURATION_BUCKETS,
+        )?
+        .install_recorder()?;
+
+    metrics::describe_histogram!(
+        REQUEST_DURATION,
+        metrics::Unit::Seconds,
+        "Request latency by route"
+    );
+    metrics::describe_counter!(REQUESTS, "Responses by route and status");
+    metrics::describe_gauge!("db_pool_connections", "Open database connections");
+    metrics::describe_gauge!(
+        "db_pool_idle_connections",
+        "Open database connections not in use"
+    );
+    metrics::describe_gauge!("db_pool_max_connections", "Configured pool size");
+    metrics::describe_counter!(
+        "polls_created_total",
+        "Polls created, including copies and polls from templates"
+    );
+    metrics::describe_counter!("polls_deleted_total", "Polls moved to the trash");
+    metrics::describe_counter!("votes_submitted_total", "Participants who answered a poll");
+    metrics::describe_counter!(
+        "votes_updated_total",
+        "Participants who changed their answers"
+    );
+    metrics::describe_counter!(
+        "webhook_deliveries_total",
+        "Webhook delivery attempts by outcome"
+    );
+    metrics::describe_counter!(
+        "poll_reads_total",
+        "Poll reads by how they were answered: not_modified, cache_hit or cache_miss"
+    );
+
+    Ok(handle)
+}
+
+/// Middleware recording latency and status per route. Routes are labelled by
+/// their pattern (`/api/poll/:id`), so ids don't blow up the label count.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::histogram!(REQUEST_DURATION, "method" => method.clone(), "route" => route.clone())
+        .record(started.elapsed().as_secs_f64());
+    metrics::counter!(REQUESTS, "method" => method, "route" => route, "status" => status)
+        .increment(1);
+
+    response
+}
+
+/// Samples the pool right before a scrape.
+pub fn record_pool(pool: &AnyPool) {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+    metrics::gauge!("db_pool_connections").set(size);
+    metrics::gauge!("db_pool_idle_connections").set(idle);
+    metrics::gauge!("db_pool_max_connections").set(pool.options().get_max_connections());
+}
+
+pub fn poll_created() {
+    metrics::counter!("polls_created_total").increment(1);
+}
+
+pub fn poll_deleted() {
+    metrics::counter!("polls_deleted_total").increment(1);
+}
+
+pub fn votes_submitted() {
+    metrics::counter!("votes_submitted_total").increment(1);
+}
+
+pub fn votes_updated() {
+    metrics::counter!("votes_updated_total").increment(1);
+}
+
+pub fn webhook_delivery(outcome: &'static str) {
+    metrics::counter!("webhook_deliveries_total", "outcome" => outcome).increment(1);
+}
+
+pub fn poll_read(outcome: &'static str) {
+    metrics::counter!("poll_reads_total", "outcome" => outcome).increment(1);
+}
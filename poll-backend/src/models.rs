@@ -1,7 +1,48 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use crate::policy::SignupMode;
+
+/// Stores an enum as its lowercase name in a TEXT column.
+macro_rules! text_column {
+    ($ty:ident { $($variant:ident => $text:literal),* $(,)? }) => {
+        impl sqlx::Type<sqlx::Any> for $ty {
+            fn type_info() -> sqlx::any::AnyTypeInfo {
+                <str as sqlx::Type<sqlx::Any>>::type_info()
+            }
+
+            fn compatible(ty: &sqlx::any::AnyTypeInfo) -> bool {
+                <str as sqlx::Type<sqlx::Any>>::compatible(ty)
+            }
+        }
+
+        impl<'q> sqlx::Encode<'q, sqlx::Any> for $ty {
+            fn encode_by_ref(
+                &self,
+                buf: &mut <sqlx::Any as sqlx::Database>::ArgumentBuffer<'q>,
+            ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+                let text = match self {
+                    $(Self::$variant => $text,)*
+                };
+                <&str as sqlx::Encode<sqlx::Any>>::encode(text, buf)
+            }
+        }
+
+        impl<'r> sqlx::Decode<'r, sqlx::Any> for $ty {
+            fn decode(
+                value: <sqlx::Any as sqlx::Database>::ValueRef<'r>,
+            ) -> Result<Self, sqlx::error::BoxDynError> {
+                match <&str as sqlx::Decode<sqlx::Any>>::decode(value)? {
+                    $($text => Ok(Self::$variant),)*
+                    other => Err(format!("unknown {} `{other}`", stringify!($ty)).into()),
+                }
+            }
+        }
+    };
+}
+
 // ── Database row types ────────────────────────────────────────────────────────
 
 #[derive(Debug, sqlx::FromRow)]
@@ -11,6 +52,8 @@ pub struct EventRow {
     pub description: Option<String>,
     pub created_at: String,
     pub admin_id: String,
+    /// Set when the event belongs to an organization rather than only its creator
+    pub organization_id: Option<String>,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -21,7 +64,7 @@ pub struct TimeSlotRow {
     pub ends_at: String,
 }
 
-#[derive(Debug, sqlx::FromRow)]
+#[derive(Debug, Clone, sqlx::FromRow)]
 pub struct ParticipantRow {
     pub id: String,
     pub event_id: String,
@@ -31,21 +74,160 @@ pub struct ParticipantRow {
 
 // ── API request / response types ──────────────────────────────────────────────
 
-#[derive(Debug, Deserialize)]
+/// What a collaborator may do with an event. Variants are ordered so that a
+/// higher role includes everything a lower one may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EventRole {
+    /// Read results and the audit log
+    Viewer,
+    /// Also moderate participants
+    Editor,
+    /// Also delete, restore and manage collaborators
+    Owner,
+}
+
+text_column!(EventRole {
+    Viewer => "viewer",
+    Editor => "editor",
+    Owner => "owner",
+});
+
+/// A member's standing in an organization, ordered like [`EventRole`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OrgRole {
+    /// See the organization's polls and create new ones for it
+    Member,
+    /// Also manage every organization poll and its members
+    Admin,
+    /// Also grant ownership and remove other owners
+    Owner,
+}
+
+text_column!(OrgRole {
+    Member => "member",
+    Admin => "admin",
+    Owner => "owner",
+});
+
+/// What an API key may do. Keys only work on routes that ask for one of these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum ApiScope {
+    #[serde(rename = "events:read")]
+    ReadEvents,
+    #[serde(rename = "polls:create")]
+    CreatePolls,
+    #[serde(rename = "polls:export")]
+    ExportPolls,
+}
+
+impl ApiScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiScope::ReadEvents => "events:read",
+            ApiScope::CreatePolls => "polls:create",
+            ApiScope::ExportPolls => "polls:export",
+        }
+    }
+
+    /// Reads the space-separated form scopes are stored in; unknown names are skipped.
+    pub fn parse_list(scopes: &str) -> Vec<ApiScope> {
+        scopes
+            .split_whitespace()
+            .filter_map(|scope| match scope {
+                "events:read" => Some(ApiScope::ReadEvents),
+                "polls:create" => Some(ApiScope::CreatePolls),
+                "polls:export" => Some(ApiScope::ExportPolls),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn join_list(scopes: &[ApiScope]) -> String {
+        scopes
+            .iter()
+            .map(|scope| scope.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Things a webhook can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum WebhookEventType {
+    #[serde(rename = "participant.created")]
+    ParticipantCreated,
+    #[serde(rename = "votes.updated")]
+    VotesUpdated,
+    #[serde(rename = "poll.deleted")]
+    PollDeleted,
+}
+
+impl WebhookEventType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEventType::ParticipantCreated => "participant.created",
+            WebhookEventType::VotesUpdated => "votes.updated",
+            WebhookEventType::PollDeleted => "poll.deleted",
+        }
+    }
+
+    /// Reads the space-separated form subscriptions are stored in; unknown names are skipped.
+    pub fn parse_list(event_types: &str) -> Vec<WebhookEventType> {
+        event_types
+            .split_whitespace()
+            .filter_map(|event_type| match event_type {
+                "participant.created" => Some(WebhookEventType::ParticipantCreated),
+                "votes.updated" => Some(WebhookEventType::VotesUpdated),
+                "poll.deleted" => Some(WebhookEventType::PollDeleted),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn join_list(event_types: &[WebhookEventType]) -> String {
+        event_types
+            .iter()
+            .map(|event_type| event_type.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Where a webhook delivery stands. Pending deliveries are retried with backoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Gave up after the last retry
+    Failed,
+}
+
+text_column!(DeliveryStatus {
+    Pending => "pending",
+    Delivered => "delivered",
+    Failed => "failed",
+});
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateEventRequest {
     pub title: String,
     pub description: Option<String>,
+    /// Create the event for an organization the admin belongs to
+    pub organization_id: Option<String>,
     /// List of time slots to create alongside the event
     pub time_slots: Vec<TimeSlotInput>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct TimeSlotInput {
     pub starts_at: DateTime<Utc>,
     pub ends_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TimeSlotResponse {
     pub id: String,
     pub starts_at: String,
@@ -54,7 +236,7 @@ pub struct TimeSlotResponse {
     pub available_count: i64, // COUNT() always returns i64 in SQLx
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ParticipantResponse {
     pub id: String,
     pub name: String,
@@ -62,13 +244,13 @@ pub struct ParticipantResponse {
     pub votes: Vec<VoteResponse>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct VoteResponse {
     pub time_slot_id: String,
     pub available: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PollResponse {
     pub id: String,
     pub title: String,
@@ -78,70 +260,469 @@ pub struct PollResponse {
     pub participants: Vec<ParticipantResponse>,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
 pub struct EventSummaryResponse {
     pub id: String,
     pub title: String,
     pub description: Option<String>,
     pub created_at: String,
+    pub organization_id: Option<String>,
+    /// Set for soft-deleted events that can still be restored
+    pub deleted_at: Option<String>,
+    /// The requesting admin's role on this event
+    pub role: EventRole,
+    pub participant_count: i64,
+    /// Start of the earliest slot that has not started yet, if any
+    pub next_slot_starts_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EventStatusFilter {
+    /// At least one slot still lies in the future
+    Open,
+    /// Every slot has already started
+    Closed,
+    /// Not supported yet, since polls can't be finalized; answered with 422
+    Finalized,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EventScope {
+    /// Every event the admin can access
+    #[default]
+    All,
+    /// Events that don't belong to any organization
+    Personal,
+    /// Events of the organization given by `organization_id`
+    Organization,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListEventsQuery {
+    #[serde(default)]
+    #[param(inline)]
+    pub scope: EventScope,
+    /// Required when `scope` is `organization`
+    pub organization_id: Option<String>,
+    /// Opaque cursor from the previous page's `next_cursor`
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    /// Case-insensitive match against title and description
+    pub q: Option<String>,
+    #[param(inline)]
+    pub status: Option<EventStatusFilter>,
+    /// Only events with a slot starting at or after this instant
+    pub from: Option<DateTime<Utc>>,
+    /// Only events with a slot starting before this instant
+    pub to: Option<DateTime<Utc>>,
+    /// List soft-deleted events awaiting purge instead of live ones
+    pub deleted: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EventListResponse {
+    /// Newest first
+    pub events: Vec<EventSummaryResponse>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct SubmitVoteRequest {
     pub participant_name: String,
     /// One entry per time slot
     pub votes: Vec<VoteInput>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct VoteInput {
     pub time_slot_id: String,
     pub available: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateVotesRequest {
     /// One entry per time slot
     pub votes: Vec<VoteInput>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SubmitVoteResponse {
     pub participant_id: String,
 }
 
-#[derive(Debug, Deserialize)]
+/// Body of a 422 for answers to slots that aren't part of the poll.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UnknownTimeSlotsResponse {
+    /// Reason phrase of the status code
+    pub error: String,
+    /// Every slot id in the request that the poll doesn't have
+    pub time_slot_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RemoveParticipantResponse {
+    pub participant_id: String,
+    /// Slot counts after the participant's votes were removed
+    pub time_slots: Vec<TimeSlotResponse>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogQuery {
+    /// Only return entries older than this id (the previous page's `next_cursor`)
+    pub before: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditEntryResponse {
+    pub id: i64,
+    pub action: String,
+    pub admin_id: Option<String>,
+    pub details: serde_json::Value,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditLogResponse {
+    /// Newest first
+    pub entries: Vec<AuditEntryResponse>,
+    /// Pass as `before` to fetch the next page; absent on the last page
+    pub next_cursor: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AddCollaboratorRequest {
+    /// Name of an existing admin account
+    pub name: String,
+    pub role: EventRole,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct CollaboratorResponse {
+    pub admin_id: String,
+    pub name: String,
+    pub role: EventRole,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateOrganizationRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct OrganizationResponse {
+    pub id: String,
+    pub name: String,
+    /// The requesting admin's role in the organization
+    pub role: OrgRole,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AddMemberRequest {
+    /// Name of an existing admin account
+    pub name: String,
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct MemberResponse {
+    pub admin_id: String,
+    pub name: String,
+    pub role: OrgRole,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DuplicatePollRequest {
+    /// Title for the copy; defaults to the source poll's title
+    pub title: Option<String>,
+    /// Shift every slot by this many minutes (may be negative)
+    #[serde(default)]
+    pub offset_minutes: i64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SaveTemplateRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateFromTemplateRequest {
+    /// Where the template's first slot should start; the others keep their
+    /// spacing relative to it
+    pub first_slot_starts_at: DateTime<Utc>,
+    /// Title for the new poll; defaults to the template's title
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct TemplateResponse {
+    pub id: String,
+    pub name: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub slot_count: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct AdminAuthRequest {
     pub name: String,
     pub password: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SignupRequest {
+    pub name: String,
+    pub password: String,
+    /// Required when signups are invite-only
+    pub invite_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AdminAuthResponse {
     pub token: String,
     pub admin_id: String,
     pub name: String,
 }
 
-#[derive(Debug, Serialize)]
+/// Answer to a correct password: either a session right away, or a
+/// challenge to complete with a second factor.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Session(AdminAuthResponse),
+    TwoFactorRequired(TwoFactorChallengeResponse),
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TwoFactorChallengeResponse {
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+    pub expires_at: String,
+}
+
+/// Second login step. Exactly one of `code` and `recovery_code` is expected.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TwoFactorLoginRequest {
+    pub challenge_token: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TwoFactorStatusResponse {
+    pub enabled: bool,
+    pub recovery_codes_left: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TotpSetupResponse {
+    /// Base32, for typing into an authenticator app by hand
+    pub secret: String,
+    /// `otpauth://` URI, usually shown as a QR code
+    pub provisioning_uri: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    /// Shown only once; each code signs in a single time
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DisableTwoFactorRequest {
+    /// Not needed by admins who signed up through the OpenID Connect provider
+    /// and never set a password, if they signed in recently
+    pub password: Option<String>,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct LogoutResponse {
     pub ok: bool,
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChangePasswordRequest {
+    /// Not needed by admins who signed up through the OpenID Connect provider
+    /// and never set a password, if they signed in recently
+    pub current_password: Option<String>,
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PasswordResetRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PasswordResetConfirmRequest {
+    /// Token from the reset link
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeleteAccountRequest {
+    /// Not needed by admins who signed up through the OpenID Connect provider
+    /// and never set a password, if they signed in recently
+    pub password: Option<String>,
+    /// Must repeat the account name to confirm the deletion
+    pub confirm_name: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InviteResponse {
+    /// Shown only once; hand it to the person who should sign up
+    pub code: String,
+    pub expires_at: String,
+}
+
+/// Public account settings, so the signup form can explain its rules.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuthConfigResponse {
+    pub signup_mode: SignupMode,
+    pub password_min_length: usize,
+    pub password_min_character_classes: usize,
+    pub oidc_enabled: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcLinkResponse {
+    /// Where to send the browser to confirm the identity with the provider
+    pub authorization_url: String,
+}
+
+/// Query the provider redirects back with.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    /// Never expires when left out
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    /// Start of the key, to tell keys apart without revealing them
+    pub prefix: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    /// Shown only once; send it as `Authorization: Bearer <key>`
+    pub key: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub events: Vec<WebhookEventType>,
+    /// Only fire for this event; every event the admin owns when left out
+    pub event_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<WebhookEventType>,
+    pub event_id: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedWebhookResponse {
+    #[serde(flatten)]
+    pub webhook: WebhookResponse,
+    /// Shown only once; verifies the `x-webhook-signature` header
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct WebhookDeliveryResponse {
+    pub id: String,
+    pub event_type: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    /// When the next retry is due, while the delivery is pending
+    pub next_attempt_at: Option<String>,
+    pub last_attempt_at: Option<String>,
+    /// HTTP status of the last answer, if the receiver answered at all
+    pub response_status: Option<i32>,
+    /// Why the receiver couldn't be reached: `blocked_address`, `timeout`,
+    /// `connection_failed` or `request_failed`
+    pub last_error: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueuedDeliveryResponse {
+    pub delivery_id: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OkResponse {
+    pub ok: bool,
+}
+
 // ── Constructors ──────────────────────────────────────────────────────────────
 
 impl EventRow {
-    pub fn new(title: String, description: Option<String>, admin_id: String) -> Self {
+    pub fn new(
+        title: String,
+        description: Option<String>,
+        admin_id: String,
+        organization_id: Option<String>,
+    ) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
             title,
             description,
             created_at: Utc::now().to_rfc3339(),
             admin_id,
+            organization_id,
         }
     }
 }
 
+impl TimeSlotInput {
+    /// Whether the slot has a positive length.
+    pub fn ends_after_start(&self) -> bool {
+        self.ends_at > self.starts_at
+    }
+}
+
 impl TimeSlotRow {
     pub fn new(event_id: &str, starts_at: DateTime<Utc>, ends_at: DateTime<Utc>) -> Self {
         Self {
@@ -163,3 +744,12 @@ impl ParticipantRow {
         }
     }
 }
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Parses a timestamp as stored in the TEXT columns written by the constructors above.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
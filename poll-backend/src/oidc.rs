@@ -0,0 +1,433 @@
+//! OpenID Connect authorization code flow with PKCE, for signing admins in
+//! through an external identity provider.
+//!
+//! The ID token comes straight from the token endpoint over TLS, so its
+//! signature is not checked (OpenID Connect Core 1.0, section 3.1.3.7); the
+//! issuer, audience, expiry and nonce claims are.
+
+use anyhow::{anyhow, bail, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use rand_core::{OsRng, RngCore};
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+
+/// Identity provider settings, read once at startup.
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer_url: String,
+    pub client_id: String,
+    /// Absent for public clients, which rely on PKCE alone
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub scopes: String,
+    /// Whether a first login from an unknown subject creates a new admin
+    pub allow_signup: bool,
+}
+
+impl OidcConfig {
+    /// Reads `OIDC_ISSUER_URL`, `OIDC_CLIENT_ID`, `OIDC_CLIENT_SECRET`,
+    /// `OIDC_REDIRECT_URL`, `OIDC_SCOPES` and `OIDC_ALLOW_SIGNUP`. Returns `None`
+    /// when no issuer is configured.
+    pub fn from_env(public_base_url: &str) -> anyhow::Result<Option<Self>> {
+        let Ok(issuer_url) = std::env::var("OIDC_ISSUER_URL") else {
+            return Ok(None);
+        };
+
+        let client_id =
+            std::env::var("OIDC_CLIENT_ID").context("OIDC_CLIENT_ID is required with OIDC_ISSUER_URL")?;
+
+        let redirect_url = std::env::var("OIDC_REDIRECT_URL").unwrap_or_else(|_| {
+            format!("{}/api/admin/oidc/callback", public_base_url.trim_end_matches('/'))
+        });
+
+        let allow_signup = match std::env::var("OIDC_ALLOW_SIGNUP") {
+            Ok(value) => value.parse().context("OIDC_ALLOW_SIGNUP must be true or false")?,
+            Err(_) => false,
+        };
+
+        Ok(Some(Self {
+            issuer_url,
+            client_id,
+            client_secret: std::env::var("OIDC_CLIENT_SECRET").ok(),
+            redirect_url,
+            scopes: std::env::var("OIDC_SCOPES").unwrap_or_else(|_| "openid profile email".into()),
+            allow_signup,
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    fn contains(&self, client_id: &str) -> bool {
+        match self {
+            Audience::One(aud) => aud == client_id,
+            Audience::Many(auds) => auds.iter().any(|aud| aud == client_id),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawClaims {
+    iss: String,
+    sub: String,
+    aud: Audience,
+    exp: i64,
+    nonce: Option<String>,
+    preferred_username: Option<String>,
+    email: Option<String>,
+    name: Option<String>,
+}
+
+/// Who signed in, once the ID token checked out.
+#[derive(Debug, Clone)]
+pub struct Identity {
+    pub issuer: String,
+    pub subject: String,
+    /// Suggested admin name for a first login
+    pub display_name: Option<String>,
+}
+
+/// Secret values for one login attempt. The caller keeps them until the
+/// provider redirects back.
+pub struct LoginAttempt {
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub authorization_url: String,
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// S256 code challenge for a PKCE verifier (RFC 7636, section 4.2).
+pub fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+pub struct OidcClient {
+    pub config: OidcConfig,
+    http: reqwest::Client,
+    /// Fetched on first use, so the server starts even while the provider is down
+    metadata: OnceCell<ProviderMetadata>,
+}
+
+impl OidcClient {
+    pub fn new(config: OidcConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+        }
+    }
+
+    async fn metadata(&self) -> anyhow::Result<&ProviderMetadata> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer_url.trim_end_matches('/')
+                );
+                let metadata: ProviderMetadata = self
+                    .http
+                    .get(&url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await
+                    .context("invalid provider metadata")?;
+
+                if metadata.issuer.trim_end_matches('/')
+                    != self.config.issuer_url.trim_end_matches('/')
+                {
+                    bail!("provider metadata names issuer `{}`", metadata.issuer);
+                }
+                Ok(metadata)
+            })
+            .await
+    }
+
+    /// Starts a login: fresh state, nonce and PKCE verifier, and the URL to
+    /// send the browser to.
+    pub async fn begin_login(&self) -> anyhow::Result<LoginAttempt> {
+        let metadata = self.metadata().await?;
+
+        let state = random_token();
+        let nonce = random_token();
+        let code_verifier = random_token();
+
+        let mut url = Url::parse(&metadata.authorization_endpoint)
+            .context("invalid authorization endpoint")?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_url)
+            .append_pair("scope", &self.config.scopes)
+            .append_pair("state", &state)
+            .append_pair("nonce", &nonce)
+            .append_pair("code_challenge", &code_challenge(&code_verifier))
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(LoginAttempt {
+            state,
+            nonce,
+            code_verifier,
+            authorization_url: url.into(),
+        })
+    }
+
+    /// Trades the authorization code for an ID token and checks its claims.
+    pub async fn finish_login(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Identity> {
+        let metadata = self.metadata().await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_url.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &self.config.client_secret {
+            form.push(("client_secret", secret));
+        }
+
+        let tokens: TokenResponse = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()
+            .context("token request was rejected")?
+            .json()
+            .await
+            .context("invalid token response")?;
+
+        self.validate_id_token(metadata, &tokens.id_token, nonce, now)
+    }
+
+    fn validate_id_token(
+        &self,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+        nonce: &str,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Identity> {
+        let payload = id_token
+            .split('.')
+            .nth(1)
+            .ok_or_else(|| anyhow!("ID token is not a JWT"))?;
+        let claims: RawClaims = serde_json::from_slice(
+            &URL_SAFE_NO_PAD
+                .decode(payload.trim_end_matches('='))
+                .context("ID token payload is not base64url")?,
+        )
+        .context("ID token payload has unexpected claims")?;
+
+        if claims.iss != metadata.issuer {
+            bail!("ID token issued by `{}`", claims.iss);
+        }
+        if !claims.aud.contains(&self.config.client_id) {
+            bail!("ID token is meant for another client");
+        }
+        if claims.exp <= now.timestamp() {
+            bail!("ID token has expired");
+        }
+        if claims.nonce.as_deref() != Some(nonce) {
+            bail!("ID token nonce does not match");
+        }
+
+        Ok(Identity {
+            issuer: claims.iss,
+            subject: claims.sub,
+            display_name: claims.preferred_username.or(claims.email).or(claims.name),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, routing::get, routing::post, Form, Json, Router};
+    use chrono::TimeZone;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    const CLIENT_ID: &str = "good-times";
+
+    /// What the mock issuer puts into its next ID token, and what it saw.
+    #[derive(Default)]
+    struct Issuer {
+        url: String,
+        nonce: String,
+        challenge: String,
+        audience: String,
+        expires_at: i64,
+        last_form: HashMap<String, String>,
+    }
+
+    type Shared = Arc<Mutex<Issuer>>;
+
+    async fn discovery(State(issuer): State<Shared>) -> Json<serde_json::Value> {
+        let url = issuer.lock().unwrap().url.clone();
+        Json(json!({
+            "issuer": url,
+            "authorization_endpoint": format!("{url}/authorize"),
+            "token_endpoint": format!("{url}/token"),
+        }))
+    }
+
+    async fn token(
+        State(issuer): State<Shared>,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> Result<Json<serde_json::Value>, axum::http::StatusCode> {
+        let mut issuer = issuer.lock().unwrap();
+        let verifier = form.get("code_verifier").cloned().unwrap_or_default();
+        issuer.last_form = form;
+        if code_challenge(&verifier) != issuer.challenge {
+            return Err(axum::http::StatusCode::BAD_REQUEST);
+        }
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(
+            json!({
+                "iss": issuer.url,
+                "sub": "user-1",
+                "aud": [issuer.audience],
+                "exp": issuer.expires_at,
+                "nonce": issuer.nonce,
+                "preferred_username": "jo",
+            })
+            .to_string(),
+        );
+        Ok(Json(json!({ "id_token": format!("{header}.{claims}."), "token_type": "Bearer" })))
+    }
+
+    async fn start_issuer() -> (Shared, OidcClient) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let issuer: Shared = Arc::new(Mutex::new(Issuer {
+            url: url.clone(),
+            audience: CLIENT_ID.into(),
+            ..Default::default()
+        }));
+
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/token", post(token))
+            .with_state(issuer.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = OidcClient::new(OidcConfig {
+            issuer_url: url,
+            client_id: CLIENT_ID.into(),
+            client_secret: None,
+            redirect_url: "http://localhost/api/admin/oidc/callback".into(),
+            scopes: "openid profile".into(),
+            allow_signup: false,
+        });
+        (issuer, client)
+    }
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(seconds, 0).unwrap()
+    }
+
+    /// Plays the provider's part of the redirect: remembers what the login URL asked for.
+    fn authorize(issuer: &Shared, attempt: &LoginAttempt) {
+        let url = Url::parse(&attempt.authorization_url).unwrap();
+        let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["code_challenge_method"], "S256");
+        assert_eq!(query["state"], attempt.state);
+
+        let mut issuer = issuer.lock().unwrap();
+        issuer.nonce = query["nonce"].clone();
+        issuer.challenge = query["code_challenge"].clone();
+        issuer.expires_at = 2_000_000_000;
+    }
+
+    #[tokio::test]
+    async fn completes_the_code_flow_with_pkce() {
+        let (issuer, client) = start_issuer().await;
+        let attempt = client.begin_login().await.unwrap();
+        authorize(&issuer, &attempt);
+
+        let identity = client
+            .finish_login("code-1", &attempt.code_verifier, &attempt.nonce, at(1_900_000_000))
+            .await
+            .unwrap();
+
+        assert_eq!(identity.subject, "user-1");
+        assert_eq!(identity.display_name.as_deref(), Some("jo"));
+        assert_eq!(issuer.lock().unwrap().last_form["code"], "code-1");
+    }
+
+    #[tokio::test]
+    async fn rejects_a_wrong_code_verifier() {
+        let (issuer, client) = start_issuer().await;
+        let attempt = client.begin_login().await.unwrap();
+        authorize(&issuer, &attempt);
+
+        let result = client
+            .finish_login("code-1", "not-the-verifier", &attempt.nonce, at(1_900_000_000))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_tokens_with_bad_claims() {
+        let (issuer, client) = start_issuer().await;
+        let attempt = client.begin_login().await.unwrap();
+        authorize(&issuer, &attempt);
+
+        // Another login's nonce
+        let result = client
+            .finish_login("code-1", &attempt.code_verifier, "other", at(1_900_000_000))
+            .await;
+        assert!(result.is_err());
+
+        // Past its expiry
+        let result = client
+            .finish_login("code-1", &attempt.code_verifier, &attempt.nonce, at(2_000_000_000))
+            .await;
+        assert!(result.is_err());
+
+        // Issued to a different client
+        issuer.lock().unwrap().audience = "someone-else".into();
+        let result = client
+            .finish_login("code-1", &attempt.code_verifier, &attempt.nonce, at(1_900_000_000))
+            .await;
+        assert!(result.is_err());
+    }
+}
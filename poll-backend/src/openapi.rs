@@ -0,0 +1,262 @@
+//! OpenAPI description of the HTTP API, put together from the handlers'
+//! `#[utoipa::path]` attributes and the request and response types. Served as
+//! JSON at `/api/openapi.json` and as browsable docs at `/api/docs`.
+
+use std::sync::OnceLock;
+
+use axum::{response::Html, Json};
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+use utoipa_scalar::Scalar;
+
+use crate::auth::SESSION_COOKIE;
+use crate::request_id::ErrorResponse;
+use crate::routes;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Good Times API",
+        description = "Scheduling polls: admins create polls with time slots, participants \
+            answer them without an account. Errors without a body of their own come as \
+            `ErrorResponse`.\n\nThe same routes are still served without the `/v1`, for \
+            clients from before versioning. Those answers carry `Deprecation`, `Sunset` and \
+            `Link` headers pointing at the `/api/v1` path."
+    ),
+    paths(
+        routes::health::healthz,
+        routes::health::readyz,
+        routes::health::prometheus_metrics,
+        routes::admin_auth::signup_admin,
+        routes::admin_auth::login_admin,
+        routes::admin_auth::auth_config,
+        routes::admin_auth::create_invite,
+        routes::two_factor::complete_login,
+        routes::admin_auth::logout_admin,
+        routes::admin_account::change_password,
+        routes::admin_account::request_password_reset,
+        routes::admin_account::confirm_password_reset,
+        routes::admin_account::delete_account,
+        routes::admin_oidc::begin_oidc_login,
+        routes::admin_oidc::oidc_callback,
+        routes::admin_oidc::begin_oidc_link,
+        routes::admin_oidc::unlink_oidc,
+        routes::api_keys::list_api_keys,
+        routes::api_keys::create_api_key,
+        routes::api_keys::delete_api_key,
+        routes::two_factor::get_two_factor_status,
+        routes::two_factor::setup_totp,
+        routes::two_factor::enable_totp,
+        routes::two_factor::disable_totp,
+        routes::two_factor::regenerate_recovery_codes,
+        routes::create_poll::create_poll,
+        routes::get_poll::get_poll,
+        routes::delete_poll::delete_poll,
+        routes::restore_poll::restore_poll,
+        routes::duplicate_poll::duplicate_poll,
+        routes::export_poll::export_poll,
+        routes::templates::save_template,
+        routes::submit_vote::submit_vote,
+        routes::update_votes::update_votes,
+        routes::remove_participant::remove_participant,
+        routes::audit_log::get_audit_log,
+        routes::collaborators::list_collaborators,
+        routes::collaborators::add_collaborator,
+        routes::collaborators::remove_collaborator,
+        routes::list_events::list_events,
+        routes::organizations::list_organizations,
+        routes::organizations::create_organization,
+        routes::organizations::list_members,
+        routes::organizations::add_member,
+        routes::organizations::remove_member,
+        routes::templates::list_templates,
+        routes::templates::delete_template,
+        routes::templates::create_poll_from_template,
+        routes::webhooks::list_webhooks,
+        routes::webhooks::create_webhook,
+        routes::webhooks::delete_webhook,
+        routes::webhooks::ping_webhook,
+        routes::webhooks::list_deliveries,
+        routes::webhooks::retry_delivery,
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "auth", description = "Admin accounts and sessions"),
+        (name = "two-factor", description = "TOTP second factor and recovery codes"),
+        (name = "api keys", description = "Keys for scripts, limited to scopes"),
+        (name = "polls", description = "Managing polls"),
+        (name = "votes", description = "What participants see and send; no account needed"),
+        (name = "collaborators", description = "Sharing a poll with other admins"),
+        (name = "organizations", description = "Groups of admins sharing their polls"),
+        (name = "templates", description = "Reusable slot layouts"),
+        (name = "webhooks", description = "Signed notifications about poll activity"),
+        (name = "health", description = "Probes and metrics for operators"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Describes the ways [`crate::auth::AdminContext`] accepts credentials.
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "session_cookie",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                SESSION_COOKIE,
+                "Set by signing in. Requests that change state must repeat the \
+                 `csrf_token` cookie in the `x-csrf-token` header, or get 403.",
+            ))),
+        );
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some(
+                        "A session token, also accepted in `x-admin-token`, or an API key \
+                         (`gtk_...`). Keys only work on operations listing one of their \
+                         scopes here, and get 403 elsewhere.",
+                    ))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Built once; the spec only changes with the binary.
+fn spec() -> &'static utoipa::openapi::OpenApi {
+    static SPEC: OnceLock<utoipa::openapi::OpenApi> = OnceLock::new();
+    SPEC.get_or_init(ApiDoc::openapi)
+}
+
+pub async fn openapi_json() -> Json<&'static utoipa::openapi::OpenApi> {
+    Json(spec())
+}
+
+/// The spec rendered by Scalar, which the page loads from a CDN.
+pub async fn docs() -> Html<&'static str> {
+    static PAGE: OnceLock<String> = OnceLock::new();
+    Html(PAGE.get_or_init(|| Scalar::new(spec().clone()).to_html()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::api_version::CURRENT_PREFIX;
+
+    const METHODS: &[&str] = &["get", "post", "put", "delete", "patch"];
+
+    /// Routes serving the spec itself rather than the API.
+    const UNDOCUMENTED: &[&str] = &["/api/openapi.json", "/api/docs"];
+
+    /// `(method, path)` pairs registered with `.route(...)` in a router's
+    /// source, nested under `prefix`, with axum's `:param` segments written
+    /// the OpenAPI way.
+    fn routed(source: &str, prefix: &str) -> BTreeSet<(String, String)> {
+        let mut routes = BTreeSet::new();
+        for (start, _) in source.match_indices(".route(") {
+            let call = &source[start + ".route(".len()..];
+            let mut depth = 1;
+            let end = call
+                .char_indices()
+                .find(|&(_, c)| {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    depth == 0
+                })
+                .map(|(end, _)| end)
+                .expect("unbalanced .route( call");
+            let call = &call[..end];
+
+            let path = call
+                .split('"')
+                .nth(1)
+                .expect("route without a path literal");
+            let path = format!("{prefix}{path}");
+            if UNDOCUMENTED.contains(&path.as_str()) {
+                continue;
+            }
+            let path = path
+                .split('/')
+                .map(|segment| match segment.strip_prefix(':') {
+                    Some(param) => format!("{{{param}}}"),
+                    None => segment.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+
+            // Method routers read `get(handler).post(other)`
+            for (open, _) in call.match_indices('(') {
+                let name = call[..open]
+                    .rsplit(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .next()
+                    .unwrap_or_default();
+                if METHODS.contains(&name) {
+                    routes.insert((name.to_string(), path.clone()));
+                }
+            }
+        }
+        routes
+    }
+
+    /// The versioned API; the legacy alias serves the same routes.
+    fn routed_api() -> BTreeSet<(String, String)> {
+        routed(include_str!("routes/mod.rs"), CURRENT_PREFIX)
+    }
+
+    fn documented() -> BTreeSet<(String, String)> {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let mut operations = BTreeSet::new();
+        for (path, item) in spec["paths"].as_object().unwrap() {
+            for method in METHODS {
+                if item.get(*method).is_some() {
+                    operations.insert((method.to_string(), path.clone()));
+                }
+            }
+        }
+        operations
+    }
+
+    #[test]
+    fn spec_matches_the_router() {
+        let mut routed = routed(include_str!("lib.rs"), "");
+        routed.extend(routed_api());
+        let documented = documented();
+        assert!(!routed.is_empty());
+
+        let undocumented: Vec<_> = routed.difference(&documented).collect();
+        let unrouted: Vec<_> = documented.difference(&routed).collect();
+        assert!(
+            undocumented.is_empty() && unrouted.is_empty(),
+            "routes missing from the spec: {undocumented:?}; \
+             documented but not routed: {unrouted:?}"
+        );
+    }
+
+    #[test]
+    fn every_schema_reference_resolves() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+
+        let text = spec.to_string();
+        let missing: Vec<_> = text
+            .match_indices("#/components/schemas/")
+            .map(|(start, prefix)| {
+                let name = &text[start + prefix.len()..];
+                &name[..name.find('"').unwrap()]
+            })
+            .filter(|name| !schemas.contains_key(*name))
+            .collect();
+        assert!(missing.is_empty(), "unresolved schemas: {missing:?}");
+    }
+}
@@ -0,0 +1,53 @@
+use async_trait::async_trait;
+
+/// Delivers password reset links to admins. Admin accounts only have a name,
+/// so how the link reaches its owner is up to the deployment.
+#[async_trait]
+pub trait ResetLinkSender: Send + Sync {
+    async fn send(&self, admin_name: &str, link: &str) -> anyhow::Result<()>;
+}
+
+/// Notes reset requests in the debug log without delivering anything. The
+/// token is left out, since anyone reading the logs could otherwise take the
+/// account over; deployments that use password resets plug in a real sender.
+pub struct LogResetLinkSender;
+
+#[async_trait]
+impl ResetLinkSender for LogResetLinkSender {
+    async fn send(&self, admin_name: &str, link: &str) -> anyhow::Result<()> {
+        tracing::debug!(admin = %admin_name, link = %redact_token(link), "Password reset requested");
+        Ok(())
+    }
+}
+
+/// `link` with the value of its `token` parameter masked.
+fn redact_token(link: &str) -> String {
+    match link.split_once("token=") {
+        Some((head, tail)) => {
+            let rest = tail.find('&').map_or("", |end| &tail[end..]);
+            format!("{head}token=[redacted]{rest}")
+        }
+        None => link.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_the_token_only() {
+        assert_eq!(
+            redact_token("https://polls.example.com/reset-password?token=abc123&x=1"),
+            "https://polls.example.com/reset-password?token=[redacted]&x=1"
+        );
+        assert_eq!(
+            redact_token("https://polls.example.com/reset-password?token=abc123"),
+            "https://polls.example.com/reset-password?token=[redacted]"
+        );
+        assert_eq!(
+            redact_token("https://polls.example.com/"),
+            "https://polls.example.com/"
+        );
+    }
+}
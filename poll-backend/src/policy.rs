@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use anyhow::bail;
+
+/// Bundled list of common and breached passwords, checked without any network calls.
+const COMMON_PASSWORDS: &str = include_str!("../data/common_passwords.txt");
+
+fn common_passwords() -> &'static HashSet<String> {
+    static SET: OnceLock<HashSet<String>> = OnceLock::new();
+    SET.get_or_init(|| {
+        COMMON_PASSWORDS
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect()
+    })
+}
+
+/// Who may create new admin accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SignupMode {
+    /// Anyone who can reach the server
+    Open,
+    /// Only holders of an invite code issued by an existing admin. The very
+    /// first admin may sign up without one so a fresh install can be set up.
+    Invite,
+    /// Nobody
+    Disabled,
+}
+
+impl FromStr for SignupMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "open" => Ok(Self::Open),
+            "invite" => Ok(Self::Invite),
+            "disabled" => Ok(Self::Disabled),
+            other => bail!("unknown signup mode `{other}`, expected open, invite or disabled"),
+        }
+    }
+}
+
+/// Rules new passwords have to satisfy.
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    /// How many of lowercase, uppercase, digits and symbols must appear
+    pub min_character_classes: usize,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 10,
+            min_character_classes: 2,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Whether `password` may be used by the admin called `name`.
+    pub fn allows(&self, password: &str, name: &str) -> bool {
+        if password.chars().count() < self.min_length {
+            return false;
+        }
+
+        let classes = [
+            password.chars().any(|c| c.is_lowercase()),
+            password.chars().any(|c| c.is_uppercase()),
+            password.chars().any(|c| c.is_numeric()),
+            password.chars().any(|c| !c.is_alphanumeric()),
+        ];
+        if classes.iter().filter(|present| **present).count() < self.min_character_classes {
+            return false;
+        }
+
+        // Very short names would match too many unrelated passwords
+        let lowered = password.to_lowercase();
+        let name = name.trim().to_lowercase();
+        if name.chars().count() >= 4 && lowered.contains(&name) {
+            return false;
+        }
+
+        !common_passwords().contains(&lowered)
+    }
+}
+
+/// Account-related settings, read once at startup.
+#[derive(Debug, Clone)]
+pub struct AuthPolicy {
+    pub signup_mode: SignupMode,
+    pub password: PasswordPolicy,
+    /// Marks session cookies `Secure`, so browsers only send them over HTTPS
+    pub secure_cookies: bool,
+}
+
+impl AuthPolicy {
+    /// Reads `SIGNUP_MODE`, `PASSWORD_MIN_LENGTH`, `PASSWORD_MIN_CHARACTER_CLASSES`
+    /// and `SESSION_COOKIE_SECURE`.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let defaults = PasswordPolicy::default();
+
+        let signup_mode = match std::env::var("SIGNUP_MODE") {
+            Ok(value) => value.parse()?,
+            Err(_) => SignupMode::Open,
+        };
+
+        let min_length = match std::env::var("PASSWORD_MIN_LENGTH") {
+            Ok(value) => value.parse()?,
+            Err(_) => defaults.min_length,
+        };
+
+        let min_character_classes = match std::env::var("PASSWORD_MIN_CHARACTER_CLASSES") {
+            Ok(value) => value.parse()?,
+            Err(_) => defaults.min_character_classes,
+        };
+        if min_character_classes > 4 {
+            bail!("PASSWORD_MIN_CHARACTER_CLASSES must be between 0 and 4");
+        }
+
+        let secure_cookies = match std::env::var("SESSION_COOKIE_SECURE") {
+            Ok(value) => value.parse()?,
+            Err(_) => true,
+        };
+
+        Ok(Self {
+            signup_mode,
+            secure_cookies,
+            password: PasswordPolicy {
+                min_length,
+                min_character_classes,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_characters_not_bytes() {
+        let policy = PasswordPolicy::default();
+        assert!(!policy.allows("short1", "alice"));
+        assert!(policy.allows("lighthouse7", "alice"));
+        // Nine characters, but more than ten bytes
+        assert!(!policy.allows("éééééééé1", "alice"));
+    }
+
+    #[test]
+    fn needs_enough_character_classes() {
+        let policy = PasswordPolicy {
+            min_length: 10,
+            min_character_classes: 3,
+        };
+        assert!(!policy.allows("lighthouse7", "alice"));
+        assert!(policy.allows("Lighthouse7", "alice"));
+        assert!(policy.allows("lighthouse7!", "alice"));
+    }
+
+    #[test]
+    fn rejects_passwords_containing_the_name() {
+        let policy = PasswordPolicy::default();
+        assert!(!policy.allows("Margaret2024", "margaret"));
+        assert!(!policy.allows("my-margaret-7", " Margaret "));
+        // Too short a name to count
+        assert!(policy.allows("joyful-river-7", "joy"));
+    }
+
+    #[test]
+    fn rejects_common_passwords_in_any_case() {
+        let policy = PasswordPolicy::default();
+        assert!(!policy.allows("password1234", "alice"));
+        assert!(!policy.allows("PASSWORD1234", "alice"));
+    }
+
+    #[test]
+    fn parses_signup_modes() {
+        assert_eq!("open".parse::<SignupMode>().unwrap(), SignupMode::Open);
+        assert_eq!(
+            " Invite ".parse::<SignupMode>().unwrap(),
+            SignupMode::Invite
+        );
+        assert_eq!(
+            "DISABLED".parse::<SignupMode>().unwrap(),
+            SignupMode::Disabled
+        );
+        assert!("closed".parse::<SignupMode>().is_err());
+    }
+}
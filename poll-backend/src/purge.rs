@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use serde_json::json;
+use sqlx::AnyPool;
+use tokio::task::JoinHandle;
+
+use crate::audit;
+use crate::db::Backend;
+use crate::shutdown::ShutdownSignal;
+
+/// How often the purge task looks for expired tombstones.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Permanently removes events that were soft-deleted longer than `retention`
+/// ago. Slots, participants and votes follow through `ON DELETE CASCADE`.
+pub async fn purge_deleted_events(pool: &AnyPool, retention: Duration) -> Result<u64, sqlx::Error> {
+    let cutoff = chrono::Duration::from_std(retention)
+        .ok()
+        .and_then(|retention| chrono::Utc::now().checked_sub_signed(retention));
+    let Some(cutoff) = cutoff else {
+        // Nothing can have been deleted that long ago
+        tracing::warn!(
+            ?retention,
+            "Retention too long to compute a cutoff, not purging"
+        );
+        return Ok(0);
+    };
+
+    let backend = Backend::of_pool(pool);
+    let mut tx = pool.begin().await?;
+
+    let sql = format!(
+        "DELETE FROM events WHERE deleted_at IS NOT NULL AND {} < {} RETURNING id",
+        backend.timestamp("deleted_at"),
+        backend.timestamp("$1"),
+    );
+    let purged: Vec<String> = sqlx::query_scalar(&sql)
+        .bind(cutoff.to_rfc3339())
+        .fetch_all(&mut *tx)
+        .await?;
+
+    for event_id in &purged {
+        audit::record(&mut *tx, Some(event_id), None, "event.purged", json!({})).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(purged.len() as u64)
+}
+
+/// Runs the purge every [`PURGE_INTERVAL`] until shutdown starts.
+pub fn spawn(pool: AnyPool, retention: Duration, mut shutdown: ShutdownSignal) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.wait() => break,
+            }
+            match purge_deleted_events(&pool, retention).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!(count, "Purged deleted events"),
+                Err(e) => tracing::error!(error = ?e, "Failed to purge deleted events"),
+            }
+        }
+    })
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use super::EventRepository;
+use crate::models::{ParticipantRow, VoteInput, VoteResponse};
+
+struct Event {
+    deleted: bool,
+    /// In chronological order
+    time_slot_ids: Vec<String>,
+}
+
+#[derive(Default)]
+struct Store {
+    events: HashMap<String, Event>,
+    participants: HashMap<String, ParticipantRow>,
+    /// Answers by participant id
+    votes: HashMap<String, Vec<VoteResponse>>,
+}
+
+/// Events kept in memory, for exercising the rules built on the repository.
+#[derive(Default)]
+pub struct InMemoryEventRepository(Mutex<Store>);
+
+impl InMemoryEventRepository {
+    pub fn add_event(&self, event_id: &str, time_slot_ids: &[&str]) {
+        self.0.lock().unwrap().events.insert(
+            event_id.to_string(),
+            Event {
+                deleted: false,
+                time_slot_ids: time_slot_ids.iter().map(|id| id.to_string()).collect(),
+            },
+        );
+    }
+
+    pub fn delete_event(&self, event_id: &str) {
+        if let Some(event) = self.0.lock().unwrap().events.get_mut(event_id) {
+            event.deleted = true;
+        }
+    }
+
+    pub fn participants(&self, event_id: &str) -> Vec<ParticipantRow> {
+        let store = self.0.lock().unwrap();
+        store
+            .participants
+            .values()
+            .filter(|p| p.event_id == event_id)
+            .cloned()
+            .collect()
+    }
+
+    /// A participant's answers as `(time_slot_id, available)`, in slot order.
+    pub fn votes(&self, participant_id: &str) -> Vec<(String, bool)> {
+        let store = self.0.lock().unwrap();
+        store
+            .votes
+            .get(participant_id)
+            .map(|votes| {
+                votes
+                    .iter()
+                    .map(|vote| (vote.time_slot_id.clone(), vote.available))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Store {
+    fn live_event(&self, event_id: &str) -> Option<&Event> {
+        self.events.get(event_id).filter(|event| !event.deleted)
+    }
+
+    fn set_votes(&mut self, event_id: &str, participant_id: &str, votes: &[VoteInput]) {
+        let order = &self.events[event_id].time_slot_ids;
+        let mut answers: Vec<VoteResponse> = Vec::new();
+        for vote in votes {
+            answers.retain(|answer| answer.time_slot_id != vote.time_slot_id);
+            answers.push(VoteResponse {
+                time_slot_id: vote.time_slot_id.clone(),
+                available: vote.available,
+            });
+        }
+        answers.sort_by_key(|answer| order.iter().position(|id| *id == answer.time_slot_id));
+        self.votes.insert(participant_id.to_string(), answers);
+    }
+}
+
+#[async_trait]
+impl EventRepository for InMemoryEventRepository {
+    async fn event_exists(&self, event_id: &str) -> Result<bool, sqlx::Error> {
+        Ok(self.0.lock().unwrap().live_event(event_id).is_some())
+    }
+
+    async fn participant_exists(
+        &self,
+        event_id: &str,
+        participant_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let store = self.0.lock().unwrap();
+        Ok(store.live_event(event_id).is_some()
+            && store
+                .participants
+                .get(participant_id)
+                .is_some_and(|p| p.event_id == event_id))
+    }
+
+    async fn unknown_time_slots(
+        &self,
+        event_id: &str,
+        time_slot_ids: &[&str],
+    ) -> Result<Vec<String>, sqlx::Error> {
+        let store = self.0.lock().unwrap();
+        let known = store
+            .events
+            .get(event_id)
+            .map(|event| event.time_slot_ids.as_slice())
+            .unwrap_or_default();
+        Ok(time_slot_ids
+            .iter()
+            .filter(|id| !known.iter().any(|known| known == *id))
+            .map(|id| id.to_string())
+            .collect())
+    }
+
+    async fn add_participant(
+        &self,
+        participant: &ParticipantRow,
+        votes: &[VoteInput],
+    ) -> Result<(), sqlx::Error> {
+        let mut store = self.0.lock().unwrap();
+        store.set_votes(&participant.event_id, &participant.id, votes);
+        store
+            .participants
+            .insert(participant.id.clone(), participant.clone());
+        Ok(())
+    }
+
+    async fn replace_votes(
+        &self,
+        event_id: &str,
+        participant_id: &str,
+        votes: &[VoteInput],
+    ) -> Result<Vec<VoteResponse>, sqlx::Error> {
+        let mut store = self.0.lock().unwrap();
+        let previous = store.votes.remove(participant_id).unwrap_or_default();
+        store.set_votes(event_id, participant_id, votes);
+        Ok(previous)
+    }
+}
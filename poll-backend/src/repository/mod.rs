@@ -0,0 +1,52 @@
+//! Storage of events and their answers, behind a trait so the rules in
+//! [`crate::votes`] can run against memory in tests.
+
+#[cfg(test)]
+mod memory;
+mod sql;
+
+use async_trait::async_trait;
+
+use crate::models::{ParticipantRow, VoteInput, VoteResponse};
+
+#[cfg(test)]
+pub use memory::InMemoryEventRepository;
+pub use sql::SqlEventRepository;
+
+#[async_trait]
+pub trait EventRepository: Send + Sync {
+    /// Whether the event exists and isn't in the trash.
+    async fn event_exists(&self, event_id: &str) -> Result<bool, sqlx::Error>;
+
+    /// Whether the participant answered the event, which isn't in the trash.
+    async fn participant_exists(
+        &self,
+        event_id: &str,
+        participant_id: &str,
+    ) -> Result<bool, sqlx::Error>;
+
+    /// The ids among `time_slot_ids` that aren't slots of the event, in the
+    /// order given.
+    async fn unknown_time_slots(
+        &self,
+        event_id: &str,
+        time_slot_ids: &[&str],
+    ) -> Result<Vec<String>, sqlx::Error>;
+
+    /// Saves a new participant with their answers, together with the audit
+    /// entry and webhook deliveries announcing them.
+    async fn add_participant(
+        &self,
+        participant: &ParticipantRow,
+        votes: &[VoteInput],
+    ) -> Result<(), sqlx::Error>;
+
+    /// Replaces a participant's answers, likewise audited and announced.
+    /// Returns the answers they had before.
+    async fn replace_votes(
+        &self,
+        event_id: &str,
+        participant_id: &str,
+        votes: &[VoteInput],
+    ) -> Result<Vec<VoteResponse>, sqlx::Error>;
+}
@@ -0,0 +1,256 @@
+use async_trait::async_trait;
+use serde_json::json;
+use sqlx::{AnyConnection, AnyPool};
+
+use super::EventRepository;
+use crate::audit;
+use crate::db::{pg_array, Backend};
+use crate::models::{ParticipantRow, VoteInput, VoteResponse, WebhookEventType};
+use crate::revision;
+use crate::webhooks;
+
+#[derive(sqlx::FromRow)]
+struct VoteRow {
+    time_slot_id: String,
+    available: i32,
+}
+
+pub struct SqlEventRepository {
+    pool: AnyPool,
+}
+
+impl SqlEventRepository {
+    pub fn new(pool: AnyPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl EventRepository for SqlEventRepository {
+    async fn event_exists(&self, event_id: &str) -> Result<bool, sqlx::Error> {
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM events WHERE id = $1 AND deleted_at IS NULL")
+                .bind(event_id)
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(count > 0)
+    }
+
+    async fn participant_exists(
+        &self,
+        event_id: &str,
+        participant_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM participants p
+            JOIN events e ON e.id = p.event_id
+            WHERE p.id = $1 AND p.event_id = $2 AND e.deleted_at IS NULL
+            "#,
+        )
+        .bind(participant_id)
+        .bind(event_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count > 0)
+    }
+
+    async fn unknown_time_slots(
+        &self,
+        event_id: &str,
+        time_slot_ids: &[&str],
+    ) -> Result<Vec<String>, sqlx::Error> {
+        if time_slot_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = match Backend::of_pool(&self.pool) {
+            Backend::Postgres => sqlx::query_scalar(
+                r#"
+                SELECT u.id
+                FROM UNNEST($2::text[]) WITH ORDINALITY AS u(id, position)
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM time_slots ts WHERE ts.id = u.id AND ts.event_id = $1
+                )
+                ORDER BY u.position
+                "#,
+            )
+            .bind(event_id)
+            .bind(pg_array(time_slot_ids)),
+            Backend::Sqlite => sqlx::query_scalar(
+                r#"
+                SELECT u.value
+                FROM json_each($2) AS u
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM time_slots ts WHERE ts.id = u.value AND ts.event_id = $1
+                )
+                ORDER BY u.key
+                "#,
+            )
+            .bind(event_id)
+            .bind(json!(time_slot_ids).to_string()),
+        };
+        query.fetch_all(&self.pool).await
+    }
+
+    async fn add_participant(
+        &self,
+        participant: &ParticipantRow,
+        votes: &[VoteInput],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO participants (id, event_id, name, created_at) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(&participant.id)
+        .bind(&participant.event_id)
+        .bind(&participant.name)
+        .bind(&participant.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        insert_votes(&mut tx, &participant.id, votes).await?;
+        revision::bump(&mut *tx, &participant.event_id).await?;
+
+        let details = json!({
+            "participant_id": participant.id,
+            "name": participant.name,
+            "votes": votes,
+        });
+        audit::record(
+            &mut *tx,
+            Some(&participant.event_id),
+            None,
+            "participant.joined",
+            details.clone(),
+        )
+        .await?;
+        webhooks::enqueue(
+            &mut tx,
+            &participant.event_id,
+            WebhookEventType::ParticipantCreated,
+            details,
+        )
+        .await?;
+
+        tx.commit().await
+    }
+
+    async fn replace_votes(
+        &self,
+        event_id: &str,
+        participant_id: &str,
+        votes: &[VoteInput],
+    ) -> Result<Vec<VoteResponse>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let previous: Vec<VoteResponse> = sqlx::query_as::<_, VoteRow>(
+            r#"
+            SELECT v.time_slot_id, v.available
+            FROM votes v
+            JOIN time_slots ts ON ts.id = v.time_slot_id
+            WHERE v.participant_id = $1 AND ts.event_id = $2
+            ORDER BY ts.starts_at ASC
+            "#,
+        )
+        .bind(participant_id)
+        .bind(event_id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|v| VoteResponse {
+            time_slot_id: v.time_slot_id,
+            available: v.available != 0,
+        })
+        .collect();
+
+        sqlx::query(
+            r#"
+            DELETE FROM votes
+            WHERE participant_id = $1
+              AND time_slot_id IN (SELECT id FROM time_slots WHERE event_id = $2)
+            "#,
+        )
+        .bind(participant_id)
+        .bind(event_id)
+        .execute(&mut *tx)
+        .await?;
+
+        insert_votes(&mut tx, participant_id, votes).await?;
+        revision::bump(&mut *tx, event_id).await?;
+
+        audit::record(
+            &mut *tx,
+            Some(event_id),
+            None,
+            "votes.updated",
+            json!({
+                "participant_id": participant_id,
+                "before": previous,
+                "after": votes,
+            }),
+        )
+        .await?;
+        webhooks::enqueue(
+            &mut tx,
+            event_id,
+            WebhookEventType::VotesUpdated,
+            json!({
+                "participant_id": participant_id,
+                "votes": votes,
+            }),
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(previous)
+    }
+}
+
+/// Inserts all answers in one statement.
+async fn insert_votes(
+    conn: &mut AnyConnection,
+    participant_id: &str,
+    votes: &[VoteInput],
+) -> Result<(), sqlx::Error> {
+    if votes.is_empty() {
+        return Ok(());
+    }
+
+    let query = match Backend::of(conn) {
+        Backend::Postgres => sqlx::query(
+            r#"
+            INSERT INTO votes (participant_id, time_slot_id, available)
+            SELECT $1, v.time_slot_id, v.available
+            FROM UNNEST($2::text[], $3::int[]) AS v(time_slot_id, available)
+            ON CONFLICT (participant_id, time_slot_id) DO UPDATE SET available = excluded.available
+            "#,
+        )
+        .bind(participant_id)
+        .bind(pg_array(votes.iter().map(|vote| &vote.time_slot_id)))
+        .bind(pg_array(votes.iter().map(|vote| vote.available as i32))),
+        // The WHERE keeps SQLite from reading ON CONFLICT as a join constraint
+        Backend::Sqlite => sqlx::query(
+            r#"
+            INSERT INTO votes (participant_id, time_slot_id, available)
+            SELECT $1, v.value ->> 0, v.value ->> 1
+            FROM json_each($2) AS v
+            WHERE true
+            ON CONFLICT (participant_id, time_slot_id) DO UPDATE SET available = excluded.available
+            "#,
+        )
+        .bind(participant_id)
+        .bind(
+            json!(votes
+                .iter()
+                .map(|vote| (&vote.time_slot_id, vote.available as i32))
+                .collect::<Vec<_>>())
+            .to_string(),
+        ),
+    };
+    query.execute(conn).await?;
+
+    Ok(())
+}
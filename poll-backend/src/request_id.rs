@@ -0,0 +1,140 @@
+//! Correlation ids. Every request gets an `x-request-id`, taken from the
+//! caller when it looks sane and generated otherwise. It names the span all
+//! of the request's log lines belong to, and comes back in the response
+//! headers and in error bodies.
+
+use std::time::Instant;
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{MatchedPath, Request},
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longer ids from callers are replaced rather than logged.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Body of error responses that don't bring their own.
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct ErrorResponse {
+    /// Reason phrase of the status code, such as `Not Found`, unless the
+    /// handler explained the failure
+    pub error: String,
+    /// Same as the `x-request-id` response header
+    pub request_id: String,
+}
+
+/// Attached to a bodiless error response, replaces the reason phrase in the
+/// body written for it.
+#[derive(Clone, Copy)]
+pub struct ErrorReason(pub &'static str);
+
+/// Keeps ids from upstream proxies so one id follows a request across
+/// services, but only if they can't mess up the logs.
+fn incoming_id(request: &Request) -> Option<String> {
+    let value = request.headers().get(&REQUEST_ID_HEADER)?.to_str().ok()?;
+    let valid = !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LENGTH
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+    valid.then(|| value.to_string())
+}
+
+/// Middleware around the whole router: assigns the id, runs the request in a
+/// span and logs one line when it completes.
+pub async fn trace_requests(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let request_id = incoming_id(&request).unwrap_or_else(|| Uuid::new_v4().to_string());
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        route = %route,
+    );
+
+    let response = next.run(request).instrument(span.clone()).await;
+    let mut response = with_error_body(response, &request_id);
+
+    let status = response.status().as_u16();
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    span.in_scope(|| {
+        if response.status().is_server_error() {
+            tracing::warn!(status, latency_ms, "Request failed");
+        } else {
+            tracing::info!(status, latency_ms, "Request completed");
+        }
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Handlers mostly fail with a bare status code. Give those responses a JSON
+/// body naming the request, so a user can quote the id in a bug report.
+fn with_error_body(response: Response, request_id: &str) -> Response {
+    let status = response.status();
+    let empty = response.body().size_hint().exact() == Some(0);
+    if !(status.is_client_error() || status.is_server_error()) || !empty {
+        return response;
+    }
+
+    let reason = match response.extensions().get::<ErrorReason>() {
+        Some(ErrorReason(reason)) => reason,
+        None => status.canonical_reason().unwrap_or("Error"),
+    };
+    let body = serde_json::to_vec(&ErrorResponse {
+        error: reason.to_string(),
+        request_id: request_id.to_string(),
+    })
+    .unwrap_or_default();
+
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_id(value: &str) -> Request {
+        Request::builder()
+            .header(REQUEST_ID_HEADER, value)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn keeps_well_formed_ids() {
+        assert_eq!(
+            incoming_id(&with_id("lb-1:7f3a.b_2")).as_deref(),
+            Some("lb-1:7f3a.b_2")
+        );
+    }
+
+    #[test]
+    fn replaces_ids_that_could_garble_logs() {
+        assert_eq!(incoming_id(&with_id("")), None);
+        assert_eq!(incoming_id(&with_id("a b")), None);
+        assert_eq!(incoming_id(&with_id(&"x".repeat(129))), None);
+    }
+}
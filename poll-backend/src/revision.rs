@@ -0,0 +1,134 @@
+//! Poll revisions. Every write that changes what `GET /poll/{id}` returns
+//! bumps the event's revision in its transaction; the route sends it as the
+//! ETag and keeps serialized polls in a [`PollCache`] by it.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use axum::body::Bytes;
+use sqlx::AnyExecutor;
+
+/// Marks the event as changed.
+pub async fn bump<'e>(executor: impl AnyExecutor<'e>, event_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE events SET revision = revision + 1, updated_at = $2 WHERE id = $1")
+        .bind(event_id)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+struct Entry {
+    revision: i64,
+    body: Bytes,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_event: HashMap<String, Entry>,
+    /// Counts lookups, to tell which entry was used least recently
+    ticks: u64,
+}
+
+/// Serialized polls, each valid for one revision. Holds at most `capacity`
+/// polls and drops the least recently used one to make room; a capacity of
+/// zero caches nothing.
+pub struct PollCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl PollCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::default(),
+        }
+    }
+
+    /// The poll as serialized at `revision`, if cached.
+    pub fn get(&self, event_id: &str, revision: i64) -> Option<Bytes> {
+        let mut guard = self.entries.lock().unwrap();
+        let entries = &mut *guard;
+        entries.ticks += 1;
+        let entry = entries
+            .by_event
+            .get_mut(event_id)
+            .filter(|entry| entry.revision == revision)?;
+        entry.last_used = entries.ticks;
+        Some(entry.body.clone())
+    }
+
+    pub fn insert(&self, event_id: &str, revision: i64, body: Bytes) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        match entries.by_event.get(event_id) {
+            // A slower request mustn't replace a newer poll
+            Some(entry) if entry.revision > revision => return,
+            Some(_) => {}
+            None if entries.by_event.len() >= self.capacity => {
+                let oldest = entries
+                    .by_event
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(id, _)| id.clone());
+                if let Some(oldest) = oldest {
+                    entries.by_event.remove(&oldest);
+                }
+            }
+            None => {}
+        }
+        entries.ticks += 1;
+        let last_used = entries.ticks;
+        entries.by_event.insert(
+            event_id.to_string(),
+            Entry {
+                revision,
+                body,
+                last_used,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serves_only_the_cached_revision() {
+        let cache = PollCache::new(10);
+        cache.insert("a", 2, Bytes::from_static(b"two"));
+        assert_eq!(cache.get("a", 2), Some(Bytes::from_static(b"two")));
+        assert_eq!(cache.get("a", 3), None);
+        assert_eq!(cache.get("b", 2), None);
+
+        cache.insert("a", 1, Bytes::from_static(b"one"));
+        assert_eq!(cache.get("a", 2), Some(Bytes::from_static(b"two")));
+        cache.insert("a", 3, Bytes::from_static(b"three"));
+        assert_eq!(cache.get("a", 3), Some(Bytes::from_static(b"three")));
+    }
+
+    #[test]
+    fn drops_the_least_recently_used_poll_when_full() {
+        let cache = PollCache::new(2);
+        cache.insert("a", 1, Bytes::from_static(b"a"));
+        cache.insert("b", 1, Bytes::from_static(b"b"));
+        cache.get("a", 1);
+        cache.insert("c", 1, Bytes::from_static(b"c"));
+
+        assert!(cache.get("a", 1).is_some());
+        assert!(cache.get("b", 1).is_none());
+        assert!(cache.get("c", 1).is_some());
+    }
+
+    #[test]
+    fn caches_nothing_without_capacity() {
+        let cache = PollCache::new(0);
+        cache.insert("a", 1, Bytes::from_static(b"a"));
+        assert_eq!(cache.get("a", 1), None);
+    }
+}
@@ -0,0 +1,429 @@
+use axum::{extract::State, http::StatusCode, Json};
+use rand_core::{OsRng, RngCore};
+use serde_json::json;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use crate::audit;
+use crate::auth::{hash_password, hash_token, verify_password, AdminContext};
+use crate::db::Backend;
+use crate::models::{
+    parse_timestamp, ChangePasswordRequest, DeleteAccountRequest, OkResponse, OrgRole,
+    PasswordResetConfirmRequest, PasswordResetRequest,
+};
+use crate::policy::AuthPolicy;
+use crate::request_id::ErrorResponse;
+use crate::state::AppState;
+
+/// How long a password reset link stays valid.
+const RESET_TOKEN_TTL_MINUTES: i64 = 60;
+/// How recently an admin without a password must have signed in through the
+/// provider to change their account.
+const FRESH_SESSION_MINUTES: i64 = 10;
+
+#[derive(sqlx::FromRow)]
+struct AdminRow {
+    name: String,
+    password_hash: String,
+    has_password: i32,
+}
+
+/// Checks that the signed-in admin is who they claim before an account change,
+/// and returns their name. Admins who never set a password, because they
+/// signed up through the OpenID Connect provider, confirm by having signed in
+/// within [`FRESH_SESSION_MINUTES`] instead.
+pub(crate) async fn confirm_identity(
+    pool: &AnyPool,
+    admin: &AdminContext,
+    password: Option<&str>,
+) -> Result<String, StatusCode> {
+    let current = sqlx::query_as::<_, AdminRow>(
+        "SELECT name, password_hash, has_password FROM admins WHERE id = $1",
+    )
+    .bind(&admin.admin_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if current.has_password != 0 {
+        verify_password(password.unwrap_or_default(), &current.password_hash)?;
+        return Ok(current.name);
+    }
+
+    let signed_in_at: Option<String> =
+        sqlx::query_scalar("SELECT created_at FROM admin_sessions WHERE id = $1 AND admin_id = $2")
+            .bind(&admin.token)
+            .bind(&admin.admin_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let fresh = signed_in_at
+        .as_deref()
+        .and_then(parse_timestamp)
+        .is_some_and(|signed_in_at| {
+            chrono::Utc::now() - signed_in_at < chrono::Duration::minutes(FRESH_SESSION_MINUTES)
+        });
+    if !fresh {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(current.name)
+}
+
+#[derive(sqlx::FromRow)]
+struct ResetTargetRow {
+    id: String,
+    name: String,
+}
+
+/// Changes the password of the signed-in admin and signs out every other session.
+/// Admins without a password set their first one here.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/password",
+    tag = "auth",
+    security(("session_cookie" = []), ("bearer" = [])),
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed; other sessions are signed out", body = OkResponse),
+        (status = 401, description = "Not signed in, the current password is wrong, or an admin without a password signed in too long ago", body = ErrorResponse),
+        (status = 422, description = "The new password breaks the password rules", body = ErrorResponse),
+    )
+)]
+pub async fn change_password(
+    State(pool): State<AnyPool>,
+    State(policy): State<Arc<AuthPolicy>>,
+    admin: AdminContext,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<Json<OkResponse>, StatusCode> {
+    let name = confirm_identity(&pool, &admin, payload.current_password.as_deref()).await?;
+
+    if !policy.password.allows(&payload.new_password, &name) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let password_hash = hash_password(&payload.new_password)?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query("UPDATE admins SET password_hash = $1, has_password = 1 WHERE id = $2")
+        .bind(&password_hash)
+        .bind(&admin.admin_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query("DELETE FROM admin_sessions WHERE admin_id = $1 AND id <> $2")
+        .bind(&admin.admin_id)
+        .bind(&admin.token)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &mut *tx,
+        None,
+        Some(&admin.admin_id),
+        "admin.password_changed",
+        json!({ "name": name }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(OkResponse { ok: true }))
+}
+
+/// Sends a reset link to the named admin. Always answers `ok` so the endpoint
+/// can't be used to probe which account names exist.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/password-reset",
+    tag = "auth",
+    request_body = PasswordResetRequest,
+    responses(
+        (status = 200, description = "Sent if the account exists", body = OkResponse),
+        (status = 422, description = "Empty name", body = ErrorResponse),
+    )
+)]
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    Json(payload): Json<PasswordResetRequest>,
+) -> Result<Json<OkResponse>, StatusCode> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let target = sqlx::query_as::<_, ResetTargetRow>("SELECT id, name FROM admins WHERE name = $1")
+        .bind(name)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let Some(target) = target else {
+        return Ok(Json(OkResponse { ok: true }));
+    };
+
+    let mut token_bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut token_bytes);
+    let token = hex::encode(token_bytes);
+
+    let now = chrono::Utc::now();
+    let expires_at = now + chrono::Duration::minutes(RESET_TOKEN_TTL_MINUTES);
+
+    sqlx::query(
+        r#"
+        INSERT INTO admin_password_resets (token_hash, admin_id, created_at, expires_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(hash_token(&token))
+    .bind(&target.id)
+    .bind(now.to_rfc3339())
+    .bind(expires_at.to_rfc3339())
+    .execute(&state.pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let link = format!(
+        "{}/reset-password?token={token}",
+        state.public_base_url.trim_end_matches('/')
+    );
+
+    if let Err(e) = state.reset_sender.send(&target.name, &link).await {
+        tracing::error!(error = ?e, admin_id = %target.id, "Failed to send password reset link");
+    }
+
+    Ok(Json(OkResponse { ok: true }))
+}
+
+/// Sets a new password from a reset token and signs out every session.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/password-reset/confirm",
+    tag = "auth",
+    request_body = PasswordResetConfirmRequest,
+    responses(
+        (status = 200, description = "Password changed; every session is signed out", body = OkResponse),
+        (status = 401, description = "Unknown, used or expired token", body = ErrorResponse),
+        (status = 422, description = "The new password breaks the password rules", body = ErrorResponse),
+    )
+)]
+pub async fn confirm_password_reset(
+    State(pool): State<AnyPool>,
+    State(policy): State<Arc<AuthPolicy>>,
+    Json(payload): Json<PasswordResetConfirmRequest>,
+) -> Result<Json<OkResponse>, StatusCode> {
+    let now = chrono::Utc::now();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Claim the token so it can only be used once. A rejected password rolls
+    // the claim back, leaving the link usable for another attempt.
+    let backend = Backend::of(&tx);
+    let sql = format!(
+        r#"
+        UPDATE admin_password_resets
+        SET used_at = $2
+        WHERE token_hash = $1
+          AND used_at IS NULL
+          AND {expires_at} > {now}
+        RETURNING admin_id AS id,
+            (SELECT a.name FROM admins a WHERE a.id = admin_password_resets.admin_id) AS name
+        "#,
+        expires_at = backend.timestamp("expires_at"),
+        now = backend.timestamp("$2"),
+    );
+    let target = sqlx::query_as::<_, ResetTargetRow>(&sql)
+        .bind(hash_token(payload.token.trim()))
+        .bind(now.to_rfc3339())
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let Some(ResetTargetRow { id: admin_id, name }) = target else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    if !policy.password.allows(&payload.new_password, &name) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let password_hash = hash_password(&payload.new_password)?;
+
+    sqlx::query("UPDATE admins SET password_hash = $1, has_password = 1 WHERE id = $2")
+        .bind(&password_hash)
+        .bind(&admin_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query("DELETE FROM admin_sessions WHERE admin_id = $1")
+        .bind(&admin_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(&mut *tx, None, Some(&admin_id), "admin.password_reset", json!({}))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(OkResponse { ok: true }))
+}
+
+/// Deletes the signed-in admin together with the polls nobody else owns.
+/// Organizations where the admin is the only member go too, their polls to
+/// the trash; being the last owner of an organization that has other members
+/// is a conflict.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/account",
+    tag = "auth",
+    security(("session_cookie" = []), ("bearer" = [])),
+    request_body = DeleteAccountRequest,
+    responses(
+        (status = 200, description = "Account deleted", body = OkResponse),
+        (status = 401, description = "Not signed in, the password is wrong, or an admin without a password signed in too long ago", body = ErrorResponse),
+        (status = 409, description = "Last owner of an organization that has other members", body = ErrorResponse),
+        (status = 422, description = "`confirm_name` doesn't match the account name", body = ErrorResponse),
+    )
+)]
+pub async fn delete_account(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Json(payload): Json<DeleteAccountRequest>,
+) -> Result<Json<OkResponse>, StatusCode> {
+    let name = confirm_identity(&pool, &admin, payload.password.as_deref()).await?;
+
+    if payload.confirm_name.trim() != name {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let orphaned_organizations: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM organization_members m
+        WHERE m.admin_id = $1
+          AND m.role = $2
+          AND NOT EXISTS (
+              SELECT 1 FROM organization_members o
+              WHERE o.organization_id = m.organization_id AND o.admin_id <> $1 AND o.role = $2
+          )
+          AND EXISTS (
+              SELECT 1 FROM organization_members o
+              WHERE o.organization_id = m.organization_id AND o.admin_id <> $1
+          )
+        "#,
+    )
+    .bind(&admin.admin_id)
+    .bind(OrgRole::Owner)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if orphaned_organizations > 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    // Personal polls where this admin is the only owner
+    let deleted_events = sqlx::query(
+        r#"
+        DELETE FROM events
+        WHERE events.organization_id IS NULL
+          AND EXISTS (
+              SELECT 1 FROM event_collaborators c
+              WHERE c.event_id = events.id AND c.admin_id = $1 AND c.role = 'owner'
+          )
+          AND NOT EXISTS (
+              SELECT 1 FROM event_collaborators c
+              WHERE c.event_id = events.id AND c.admin_id <> $1 AND c.role = 'owner'
+          )
+        "#,
+    )
+    .bind(&admin.admin_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .rows_affected();
+
+    // Organizations with no other members go now. Their polls go to the trash
+    // like any deleted poll, until the purge task removes them
+    let lone_organizations = r#"
+        SELECT m.organization_id
+        FROM organization_members m
+        WHERE m.admin_id = $1
+          AND NOT EXISTS (
+              SELECT 1 FROM organization_members o
+              WHERE o.organization_id = m.organization_id AND o.admin_id <> $1
+          )
+    "#;
+
+    let trashed_events = sqlx::query(&format!(
+        r#"
+        UPDATE events
+        SET deleted_at = $2, revision = revision + 1, updated_at = $2
+        WHERE deleted_at IS NULL AND organization_id IN ({lone_organizations})
+        "#
+    ))
+    .bind(&admin.admin_id)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .rows_affected();
+
+    let deleted_organizations = sqlx::query(&format!(
+        "DELETE FROM organizations WHERE id IN ({lone_organizations})"
+    ))
+    .bind(&admin.admin_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .rows_affected();
+
+    // Sessions, collaborator grants, memberships and templates cascade
+    sqlx::query("DELETE FROM admins WHERE id = $1")
+        .bind(&admin.admin_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &mut *tx,
+        None,
+        Some(&admin.admin_id),
+        "admin.deleted",
+        json!({
+            "name": name,
+            "deleted_events": deleted_events,
+            "trashed_events": trashed_events,
+            "deleted_organizations": deleted_organizations,
+        }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(OkResponse { ok: true }))
+}
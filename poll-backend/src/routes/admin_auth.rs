@@ -1,13 +1,35 @@
-use axum::{extract::State, http::HeaderMap, http::StatusCode, Json};
-use sqlx::PgPool;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use rand_core::{OsRng, RngCore};
+use serde_json::json;
+use sqlx::AnyPool;
+use std::sync::Arc;
 
-use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
-use argon2::Argon2;
-use rand_core::OsRng;
 use uuid::Uuid;
 
-use crate::auth::require_admin;
-use crate::models::{AdminAuthRequest, AdminAuthResponse, LogoutResponse};
+use crate::audit;
+use crate::auth::{
+    clear_session_cookies, create_session, hash_password, hash_token, session_cookies,
+    verify_password, AdminContext, SessionCookies,
+};
+use crate::client_ip::ClientIp;
+use crate::clock::Clock;
+use crate::db::{self, Backend};
+use crate::models::{
+    AdminAuthRequest, AdminAuthResponse, AuthConfigResponse, InviteResponse, LoginResponse,
+    LogoutResponse, SignupRequest,
+};
+use crate::policy::{AuthPolicy, SignupMode};
+use crate::request_id::ErrorResponse;
+use crate::routes::two_factor::{create_login_challenge, two_factor_enabled};
+use crate::state::AppState;
+
+/// How long an invite code can be used to sign up.
+const INVITE_TTL_DAYS: i64 = 7;
 
 #[derive(sqlx::FromRow)]
 struct AdminRow {
@@ -16,29 +38,84 @@ struct AdminRow {
     password_hash: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/signup",
+    tag = "auth",
+    request_body = SignupRequest,
+    responses(
+        (status = 200, description = "Signed up and signed in; sets the session cookies", body = AdminAuthResponse),
+        (status = 403, description = "Signups are disabled, or the invite code is missing or invalid", body = ErrorResponse),
+        (status = 409, description = "The name is taken", body = ErrorResponse),
+        (status = 422, description = "Empty name, or the password breaks the password rules", body = ErrorResponse),
+    )
+)]
 pub async fn signup_admin(
-    State(pool): State<PgPool>,
-    Json(payload): Json<AdminAuthRequest>,
-) -> Result<Json<AdminAuthResponse>, StatusCode> {
+    State(pool): State<AnyPool>,
+    State(policy): State<Arc<AuthPolicy>>,
+    Json(payload): Json<SignupRequest>,
+) -> Result<(SessionCookies, Json<AdminAuthResponse>), StatusCode> {
+    if policy.signup_mode == SignupMode::Disabled {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     let name = payload.name.trim();
-    if name.is_empty() || payload.password.is_empty() {
+    if name.is_empty() || !policy.password.allows(&payload.password, name) {
         return Err(StatusCode::UNPROCESSABLE_ENTITY);
     }
 
-    let salt = SaltString::generate(&mut OsRng);
-    let password_hash = Argon2::default()
-        .hash_password(payload.password.as_bytes(), &salt)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .to_string();
+    let password_hash = hash_password(&payload.password)?;
 
     let admin_id = Uuid::new_v4().to_string();
-    let token = Uuid::new_v4().to_string();
     let now = chrono::Utc::now().to_rfc3339();
 
-    let mut tx = pool
-        .begin()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // The first admin of a fresh install has nobody to invite them. Counting
+    // the admins locks no row, so the table is held until the new admin is
+    // in; otherwise two signups could both see an empty table.
+    let tx = if policy.signup_mode == SignupMode::Invite {
+        db::begin_exclusive(&pool, "admins").await
+    } else {
+        pool.begin().await
+    };
+    let mut tx = tx.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if policy.signup_mode == SignupMode::Invite {
+        let admin_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM admins")
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        if admin_count > 0 {
+            let code = payload
+                .invite_code
+                .as_deref()
+                .map(str::trim)
+                .filter(|code| !code.is_empty())
+                .ok_or(StatusCode::FORBIDDEN)?;
+
+            let backend = Backend::of(&tx);
+            let sql = format!(
+                r#"
+                UPDATE admin_invites
+                SET used_at = $2, used_by = $3
+                WHERE code_hash = $1 AND used_at IS NULL AND {expires_at} > {now}
+                "#,
+                expires_at = backend.timestamp("expires_at"),
+                now = backend.timestamp("$2"),
+            );
+            let claimed = sqlx::query(&sql)
+                .bind(hash_token(code))
+                .bind(&now)
+                .bind(&admin_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            if claimed.rows_affected() == 0 {
+                return Err(StatusCode::FORBIDDEN);
+            }
+        }
+    }
 
     let admin_insert = sqlx::query(
         "INSERT INTO admins (id, name, password_hash, created_at) VALUES ($1, $2, $3, $4)",
@@ -59,11 +136,11 @@ pub async fn signup_admin(
         return Err(StatusCode::INTERNAL_SERVER_ERROR);
     }
 
-    sqlx::query("INSERT INTO admin_sessions (id, admin_id, created_at) VALUES ($1, $2, $3)")
-        .bind(&token)
-        .bind(&admin_id)
-        .bind(&now)
-        .execute(&mut *tx)
+    let session = create_session(&mut *tx, &admin_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(&mut *tx, None, Some(&admin_id), "admin.signup", json!({ "name": name }))
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -71,17 +148,36 @@ pub async fn signup_admin(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(AdminAuthResponse {
-        token,
-        admin_id,
-        name: name.to_string(),
-    }))
+    Ok((
+        session_cookies(&session, policy.secure_cookies),
+        Json(AdminAuthResponse {
+            token: session.token,
+            admin_id,
+            name: name.to_string(),
+        }),
+    ))
 }
 
+/// Checks the password. Admins with two-factor authentication get a challenge
+/// to finish with `complete_login` instead of a session.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/login",
+    tag = "auth",
+    request_body = AdminAuthRequest,
+    responses(
+        (status = 200, description = "A session, with the session cookies set, or a two-factor challenge", body = LoginResponse),
+        (status = 401, description = "Wrong name or password", body = ErrorResponse),
+        (status = 422, description = "Empty name or password", body = ErrorResponse),
+    )
+)]
 pub async fn login_admin(
-    State(pool): State<PgPool>,
+    State(pool): State<AnyPool>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(policy): State<Arc<AuthPolicy>>,
+    ClientIp(ip): ClientIp,
     Json(payload): Json<AdminAuthRequest>,
-) -> Result<Json<AdminAuthResponse>, StatusCode> {
+) -> Result<Response, StatusCode> {
     let name = payload.name.trim();
     if name.is_empty() || payload.password.is_empty() {
         return Err(StatusCode::UNPROCESSABLE_ENTITY);
@@ -96,37 +192,58 @@ pub async fn login_admin(
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    let parsed_hash = PasswordHash::new(&admin.password_hash)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Argon2::default()
-        .verify_password(payload.password.as_bytes(), &parsed_hash)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    verify_password(&payload.password, &admin.password_hash)?;
 
-    let token = Uuid::new_v4().to_string();
-    let now = chrono::Utc::now().to_rfc3339();
+    if two_factor_enabled(&pool, &admin.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        let challenge = create_login_challenge(&pool, &admin.id, clock.now())
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Ok(Json(LoginResponse::TwoFactorRequired(challenge)).into_response());
+    }
 
-    sqlx::query("INSERT INTO admin_sessions (id, admin_id, created_at) VALUES ($1, $2, $3)")
-        .bind(&token)
-        .bind(&admin.id)
-        .bind(&now)
-        .execute(&pool)
+    let session = create_session(&pool, &admin.id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(AdminAuthResponse {
-        token,
-        admin_id: admin.id,
-        name: admin.name,
-    }))
+    audit::record(
+        &pool,
+        None,
+        Some(&admin.id),
+        "admin.login",
+        json!({ "name": admin.name, "ip": ip }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((
+        session_cookies(&session, policy.secure_cookies),
+        Json(LoginResponse::Session(AdminAuthResponse {
+            token: session.token,
+            admin_id: admin.id,
+            name: admin.name,
+        })),
+    )
+        .into_response())
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/logout",
+    tag = "auth",
+    security(("session_cookie" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "Signed out; clears the session cookies", body = LogoutResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
 pub async fn logout_admin(
-    State(pool): State<PgPool>,
-    headers: HeaderMap,
-) -> Result<Json<LogoutResponse>, StatusCode> {
-    let admin = require_admin(&pool, &headers).await?;
-
+    State(pool): State<AnyPool>,
+    State(policy): State<Arc<AuthPolicy>>,
+    admin: AdminContext,
+) -> Result<(SessionCookies, Json<LogoutResponse>), StatusCode> {
     let result = sqlx::query("DELETE FROM admin_sessions WHERE id = $1")
         .bind(&admin.token)
         .execute(&pool)
@@ -137,5 +254,66 @@ pub async fn logout_admin(
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    Ok(Json(LogoutResponse { ok: true }))
+    Ok((
+        clear_session_cookies(policy.secure_cookies),
+        Json(LogoutResponse { ok: true }),
+    ))
+}
+
+/// Issues a single-use invite code for signing up while signups are invite-only.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/invites",
+    tag = "auth",
+    security(("session_cookie" = []), ("bearer" = [])),
+    responses(
+        (status = 201, description = "A new invite code", body = InviteResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+pub async fn create_invite(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+) -> Result<(StatusCode, Json<InviteResponse>), StatusCode> {
+    let mut code_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut code_bytes);
+    let code = hex::encode(code_bytes);
+
+    let now = chrono::Utc::now();
+    let expires_at = (now + chrono::Duration::days(INVITE_TTL_DAYS)).to_rfc3339();
+
+    sqlx::query(
+        "INSERT INTO admin_invites (code_hash, created_by, created_at, expires_at) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(hash_token(&code))
+    .bind(&admin.admin_id)
+    .bind(now.to_rfc3339())
+    .bind(&expires_at)
+    .execute(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(&pool, None, Some(&admin.admin_id), "admin.invite_created", json!({}))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, Json(InviteResponse { code, expires_at })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/auth-config",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Signup and password rules", body = AuthConfigResponse),
+    )
+)]
+pub async fn auth_config(State(state): State<AppState>) -> Json<AuthConfigResponse> {
+    let policy = &state.auth_policy;
+    Json(AuthConfigResponse {
+        signup_mode: policy.signup_mode,
+        password_min_length: policy.password.min_length,
+        password_min_character_classes: policy.password.min_character_classes,
+        oidc_enabled: state.oidc.is_some(),
+    })
 }
@@ -0,0 +1,480 @@
+use axum::{
+    extract::{Query, State},
+    http::{header::SET_COOKIE, HeaderMap, HeaderName, StatusCode},
+    response::{AppendHeaders, IntoResponse, Redirect, Response},
+    Json,
+};
+use cookie::{Cookie, SameSite};
+use rand_core::{OsRng, RngCore};
+use serde_json::json;
+use sqlx::AnyPool;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::{
+    cookie_value, create_session, hash_password, hash_token, session_cookies, AdminContext, Session,
+};
+use crate::client_ip::ClientIp;
+use crate::db::Backend;
+use crate::models::{OidcCallbackQuery, OidcLinkResponse, OkResponse};
+use crate::oidc::{Identity, OidcClient};
+use crate::policy::SignupMode;
+use crate::request_id::ErrorResponse;
+use crate::routes::two_factor::{create_login_challenge, two_factor_enabled};
+use crate::state::AppState;
+
+/// How long the provider may take to send the browser back.
+const LOGIN_STATE_TTL_MINUTES: i64 = 10;
+/// HttpOnly cookie tying a login to the browser that started it. Without it
+/// a callback URL could be finished in someone else's browser, signing them
+/// in as the attacker or linking their identity to the attacker's account.
+const BINDING_COOKIE: &str = "oidc_binding";
+
+/// `Set-Cookie` header for the login binding; an empty value removes it.
+type BindingCookie = AppendHeaders<[(HeaderName, String); 1]>;
+
+fn binding_cookie(value: String, secure: bool) -> BindingCookie {
+    let mut cookie = Cookie::build((BINDING_COOKIE, value.clone()))
+        .path("/")
+        .http_only(true)
+        .secure(secure)
+        .same_site(SameSite::Lax)
+        .max_age(cookie::time::Duration::minutes(LOGIN_STATE_TTL_MINUTES))
+        .build();
+    if value.is_empty() {
+        cookie.make_removal();
+    }
+    AppendHeaders([(SET_COOKIE, cookie.to_string())])
+}
+
+#[derive(sqlx::FromRow)]
+struct LoginStateRow {
+    code_verifier: String,
+    nonce: String,
+    link_admin_id: Option<String>,
+}
+
+/// Outcome of a callback. A new session goes into the cookies; the frontend
+/// learns the rest from the URL fragment.
+enum CallbackOutcome {
+    Session { session: Session, name: String },
+    /// The admin has two-factor authentication; the login finishes with
+    /// `POST /admin/login/2fa` like a password login
+    TwoFactorRequired { challenge_token: String },
+    Linked,
+}
+
+fn oidc_client(state: &AppState) -> Result<&OidcClient, StatusCode> {
+    state.oidc.as_deref().ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Starts a login with the provider and remembers its secrets until the
+/// callback. Returns where to send the browser and the cookie binding it.
+async fn begin(
+    state: &AppState,
+    oidc: &OidcClient,
+    link_admin_id: Option<&str>,
+) -> Result<(String, BindingCookie), StatusCode> {
+    let pool = &state.pool;
+    let attempt = oidc.begin_login().await.map_err(|e| {
+        tracing::error!(error = ?e, "Failed to start OpenID Connect login");
+        StatusCode::BAD_GATEWAY
+    })?;
+
+    let now = chrono::Utc::now();
+    let expires_at = now + chrono::Duration::minutes(LOGIN_STATE_TTL_MINUTES);
+
+    // Drop abandoned attempts while we're here
+    let backend = Backend::of_pool(pool);
+    let sql = format!(
+        "DELETE FROM oidc_login_states WHERE {} <= {}",
+        backend.timestamp("expires_at"),
+        backend.timestamp("$1"),
+    );
+    sqlx::query(&sql)
+        .bind(now.to_rfc3339())
+        .execute(pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut binding = [0u8; 32];
+    OsRng.fill_bytes(&mut binding);
+    let binding = hex::encode(binding);
+
+    sqlx::query(
+        r#"
+        INSERT INTO oidc_login_states
+            (state_hash, code_verifier, nonce, link_admin_id, browser_hash, created_at, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(hash_token(&attempt.state))
+    .bind(&attempt.code_verifier)
+    .bind(&attempt.nonce)
+    .bind(link_admin_id)
+    .bind(hash_token(&binding))
+    .bind(now.to_rfc3339())
+    .bind(expires_at.to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((
+        attempt.authorization_url,
+        binding_cookie(binding, state.auth_policy.secure_cookies),
+    ))
+}
+
+/// Sends the browser to the identity provider.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/oidc/login",
+    tag = "auth",
+    responses(
+        (status = 303, description = "Redirect to the identity provider"),
+        (status = 404, description = "OpenID Connect is not configured", body = ErrorResponse),
+        (status = 502, description = "The identity provider could not be reached", body = ErrorResponse),
+    )
+)]
+pub async fn begin_oidc_login(
+    State(state): State<AppState>,
+) -> Result<(BindingCookie, Redirect), StatusCode> {
+    let oidc = oidc_client(&state)?;
+    let (authorization_url, cookie) = begin(&state, oidc, None).await?;
+    Ok((cookie, Redirect::to(&authorization_url)))
+}
+
+/// Links an external identity to the signed-in admin. The frontend sends the
+/// browser to the returned URL; the callback does the linking, in the same
+/// browser only.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/oidc/link",
+    tag = "auth",
+    security(("session_cookie" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "Where to send the browser", body = OidcLinkResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "OpenID Connect is not configured", body = ErrorResponse),
+        (status = 502, description = "The identity provider could not be reached", body = ErrorResponse),
+    )
+)]
+pub async fn begin_oidc_link(
+    State(state): State<AppState>,
+    admin: AdminContext,
+) -> Result<(BindingCookie, Json<OidcLinkResponse>), StatusCode> {
+    let oidc = oidc_client(&state)?;
+    let (authorization_url, cookie) = begin(&state, oidc, Some(&admin.admin_id)).await?;
+    Ok((cookie, Json(OidcLinkResponse { authorization_url })))
+}
+
+/// Removes every external identity linked to the signed-in admin.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/oidc/link",
+    tag = "auth",
+    security(("session_cookie" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "Identities unlinked", body = OkResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No identity is linked", body = ErrorResponse),
+    )
+)]
+pub async fn unlink_oidc(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+) -> Result<Json<OkResponse>, StatusCode> {
+    let result = sqlx::query("DELETE FROM admin_identities WHERE admin_id = $1")
+        .bind(&admin.admin_id)
+        .execute(&pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit::record(&pool, None, Some(&admin.admin_id), "admin.oidc_unlinked", json!({}))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(OkResponse { ok: true }))
+}
+
+/// Where the provider sends the browser back to. Always redirects to the
+/// frontend, with either a session or an error code in the fragment, and
+/// drops the login's binding cookie.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/oidc/callback",
+    tag = "auth",
+    params(OidcCallbackQuery),
+    responses(
+        (status = 303, description = "Redirect to the frontend, with a session or an error code in the fragment"),
+    )
+)]
+pub async fn oidc_callback(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Response {
+    let base = state.public_base_url.trim_end_matches('/');
+    let binding = cookie_value(&headers, BINDING_COOKIE);
+    let clear_binding = binding_cookie(String::new(), state.auth_policy.secure_cookies);
+
+    let response = match complete_callback(&state, query, binding.as_deref(), ip).await {
+        Ok(CallbackOutcome::Session { session, name }) => {
+            let fragment = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("oidc_name", &name)
+                .finish();
+            (
+                session_cookies(&session, state.auth_policy.secure_cookies),
+                Redirect::to(&format!("{base}/#{fragment}")),
+            )
+                .into_response()
+        }
+        // The token stays in the browser, is useless without a code and
+        // expires within minutes
+        Ok(CallbackOutcome::TwoFactorRequired { challenge_token }) => {
+            Redirect::to(&format!("{base}/#oidc_challenge={challenge_token}")).into_response()
+        }
+        Ok(CallbackOutcome::Linked) => {
+            Redirect::to(&format!("{base}/admin#oidc_linked=1")).into_response()
+        }
+        Err(code) => Redirect::to(&format!("{base}/#oidc_error={code}")).into_response(),
+    };
+    (clear_binding, response).into_response()
+}
+
+async fn complete_callback(
+    state: &AppState,
+    query: OidcCallbackQuery,
+    binding: Option<&str>,
+    ip: Option<IpAddr>,
+) -> Result<CallbackOutcome, &'static str> {
+    let oidc = state.oidc.as_deref().ok_or("disabled")?;
+
+    if query.error.is_some() {
+        return Err("denied");
+    }
+    let (Some(code), Some(login_state)) = (query.code, query.state) else {
+        return Err("invalid_request");
+    };
+    let binding = binding.ok_or("invalid_state")?;
+
+    let now = state.clock.now();
+
+    // Claiming the state makes every callback single-use
+    let backend = Backend::of_pool(&state.pool);
+    let sql = format!(
+        r#"
+        DELETE FROM oidc_login_states
+        WHERE state_hash = $1 AND browser_hash = $3 AND {expires_at} > {now}
+        RETURNING code_verifier, nonce, link_admin_id
+        "#,
+        expires_at = backend.timestamp("expires_at"),
+        now = backend.timestamp("$2"),
+    );
+    let pending = sqlx::query_as::<_, LoginStateRow>(&sql)
+        .bind(hash_token(&login_state))
+        .bind(now.to_rfc3339())
+        .bind(hash_token(binding))
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(|_| "server_error")?
+        .ok_or("invalid_state")?;
+
+    let identity = oidc
+        .finish_login(&code, &pending.code_verifier, &pending.nonce, now)
+        .await
+        .map_err(|e| {
+            tracing::warn!(error = ?e, "OpenID Connect login failed");
+            "provider_error"
+        })?;
+
+    match pending.link_admin_id {
+        Some(admin_id) => link_identity(&state.pool, &identity, &admin_id).await,
+        None => {
+            let signup_mode = state.auth_policy.signup_mode;
+            sign_in(&state.pool, oidc, signup_mode, &identity, now, ip).await
+        }
+    }
+}
+
+async fn link_identity(
+    pool: &AnyPool,
+    identity: &Identity,
+    admin_id: &str,
+) -> Result<CallbackOutcome, &'static str> {
+    let inserted = sqlx::query(
+        r#"
+        INSERT INTO admin_identities (issuer, subject, admin_id, created_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (issuer, subject) DO NOTHING
+        "#,
+    )
+    .bind(&identity.issuer)
+    .bind(&identity.subject)
+    .bind(admin_id)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(|_| "server_error")?;
+
+    if inserted.rows_affected() == 0 {
+        let owner: Option<String> = sqlx::query_scalar(
+            "SELECT admin_id FROM admin_identities WHERE issuer = $1 AND subject = $2",
+        )
+        .bind(&identity.issuer)
+        .bind(&identity.subject)
+        .fetch_optional(pool)
+        .await
+        .map_err(|_| "server_error")?;
+
+        // Linking the same identity twice is fine; taking over another admin's is not
+        if owner.as_deref() != Some(admin_id) {
+            return Err("already_linked");
+        }
+        return Ok(CallbackOutcome::Linked);
+    }
+
+    audit::record(
+        pool,
+        None,
+        Some(admin_id),
+        "admin.oidc_linked",
+        json!({ "issuer": identity.issuer, "subject": identity.subject }),
+    )
+    .await
+    .map_err(|_| "server_error")?;
+
+    Ok(CallbackOutcome::Linked)
+}
+
+/// Signs in the admin linked to `identity`, creating one first when the
+/// provider is allowed to sign up new admins and signups are open. There is
+/// no way to hand over an invite code here, so invite-only installs need the
+/// identity linked to an existing admin. Admins with two-factor
+/// authentication get a challenge instead of a session, as with a password.
+async fn sign_in(
+    pool: &AnyPool,
+    oidc: &OidcClient,
+    signup_mode: SignupMode,
+    identity: &Identity,
+    now: chrono::DateTime<chrono::Utc>,
+    ip: Option<IpAddr>,
+) -> Result<CallbackOutcome, &'static str> {
+    let mut tx = pool.begin().await.map_err(|_| "server_error")?;
+
+    let linked: Option<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT a.id, a.name
+        FROM admin_identities i
+        JOIN admins a ON a.id = i.admin_id
+        WHERE i.issuer = $1 AND i.subject = $2
+        "#,
+    )
+    .bind(&identity.issuer)
+    .bind(&identity.subject)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| "server_error")?;
+
+    let (admin_id, name) = match linked {
+        Some(admin) => admin,
+        None if oidc.config.allow_signup && signup_mode == SignupMode::Open => {
+            let name = identity
+                .display_name
+                .as_deref()
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .unwrap_or(&identity.subject)
+                .to_string();
+            let admin_id = Uuid::new_v4().to_string();
+            let now = chrono::Utc::now().to_rfc3339();
+
+            // The account signs in through the provider; nobody knows this password
+            let mut password = [0u8; 32];
+            OsRng.fill_bytes(&mut password);
+            let password_hash =
+                hash_password(&hex::encode(password)).map_err(|_| "server_error")?;
+
+            let admin_insert = sqlx::query(
+                "INSERT INTO admins (id, name, password_hash, created_at, has_password) VALUES ($1, $2, $3, $4, 0)",
+            )
+            .bind(&admin_id)
+            .bind(&name)
+            .bind(&password_hash)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await;
+
+            if let Err(err) = admin_insert {
+                if let sqlx::Error::Database(db_err) = &err {
+                    if db_err.is_unique_violation() {
+                        return Err("name_taken");
+                    }
+                }
+                return Err("server_error");
+            }
+
+            sqlx::query(
+                "INSERT INTO admin_identities (issuer, subject, admin_id, created_at) VALUES ($1, $2, $3, $4)",
+            )
+            .bind(&identity.issuer)
+            .bind(&identity.subject)
+            .bind(&admin_id)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| "server_error")?;
+
+            audit::record(
+                &mut *tx,
+                None,
+                Some(&admin_id),
+                "admin.signup",
+                json!({ "name": name, "oidc_issuer": identity.issuer }),
+            )
+            .await
+            .map_err(|_| "server_error")?;
+
+            (admin_id, name)
+        }
+        None if oidc.config.allow_signup => return Err("signup_closed"),
+        None => return Err("not_linked"),
+    };
+
+    if two_factor_enabled(&mut *tx, &admin_id)
+        .await
+        .map_err(|_| "server_error")?
+    {
+        tx.commit().await.map_err(|_| "server_error")?;
+        let challenge = create_login_challenge(pool, &admin_id, now)
+            .await
+            .map_err(|_| "server_error")?;
+        return Ok(CallbackOutcome::TwoFactorRequired {
+            challenge_token: challenge.challenge_token,
+        });
+    }
+
+    let session = create_session(&mut *tx, &admin_id)
+        .await
+        .map_err(|_| "server_error")?;
+
+    audit::record(
+        &mut *tx,
+        None,
+        Some(&admin_id),
+        "admin.login",
+        json!({ "name": name, "oidc_issuer": identity.issuer, "ip": ip }),
+    )
+    .await
+    .map_err(|_| "server_error")?;
+
+    tx.commit().await.map_err(|_| "server_error")?;
+
+    Ok(CallbackOutcome::Session { session, name })
+}
@@ -0,0 +1,211 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use rand_core::{OsRng, RngCore};
+use serde_json::json;
+use sqlx::AnyPool;
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::{hash_token, AdminContext, API_KEY_PREFIX};
+use crate::models::{ApiKeyResponse, ApiScope, CreateApiKeyRequest, CreatedApiKeyResponse};
+use crate::request_id::ErrorResponse;
+
+/// Characters of the key kept in the clear for telling keys apart.
+const KEY_PREFIX_LENGTH: usize = 12;
+
+#[derive(sqlx::FromRow)]
+struct ApiKeyRow {
+    id: String,
+    name: String,
+    key_prefix: String,
+    scopes: String,
+    created_at: String,
+    expires_at: Option<String>,
+    last_used_at: Option<String>,
+}
+
+impl From<ApiKeyRow> for ApiKeyResponse {
+    fn from(row: ApiKeyRow) -> Self {
+        ApiKeyResponse {
+            id: row.id,
+            name: row.name,
+            prefix: row.key_prefix,
+            scopes: ApiScope::parse_list(&row.scopes),
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+            last_used_at: row.last_used_at,
+        }
+    }
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct DeleteApiKeyResponse {
+    pub id: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/api-keys",
+    tag = "api keys",
+    security(("session_cookie" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "The signed-in admin's keys", body = Vec<ApiKeyResponse>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+pub async fn list_api_keys(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+) -> Result<Json<Vec<ApiKeyResponse>>, StatusCode> {
+    let keys = sqlx::query_as::<_, ApiKeyRow>(
+        r#"
+        SELECT id, name, key_prefix, scopes, created_at, expires_at, last_used_at
+        FROM api_keys
+        WHERE admin_id = $1
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(&admin.admin_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(keys.into_iter().map(ApiKeyResponse::from).collect()))
+}
+
+/// Issues a key acting as the signed-in admin, limited to the given scopes.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/api-keys",
+    tag = "api keys",
+    security(("session_cookie" = []), ("bearer" = [])),
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "The new key, shown only in this response", body = CreatedApiKeyResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 422, description = "Empty name, no scopes, or an expiry in the past", body = ErrorResponse),
+    )
+)]
+pub async fn create_api_key(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreatedApiKeyResponse>), StatusCode> {
+    let name = payload.name.trim();
+    if name.is_empty() || payload.scopes.is_empty() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let now = chrono::Utc::now();
+    if payload.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let mut scopes = payload.scopes;
+    scopes.sort_by_key(|scope| scope.as_str());
+    scopes.dedup();
+
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    let key = format!("{API_KEY_PREFIX}{}", hex::encode(secret));
+
+    let api_key = ApiKeyResponse {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        prefix: key[..KEY_PREFIX_LENGTH].to_string(),
+        scopes,
+        created_at: now.to_rfc3339(),
+        expires_at: payload.expires_at.map(|expires_at| expires_at.to_rfc3339()),
+        last_used_at: None,
+    };
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO api_keys (id, admin_id, name, key_prefix, key_hash, scopes, created_at, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+    )
+    .bind(&api_key.id)
+    .bind(&admin.admin_id)
+    .bind(&api_key.name)
+    .bind(&api_key.prefix)
+    .bind(hash_token(&key))
+    .bind(ApiScope::join_list(&api_key.scopes))
+    .bind(&api_key.created_at)
+    .bind(&api_key.expires_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, "Failed to insert API key");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    audit::record(
+        &mut *tx,
+        None,
+        Some(&admin.admin_id),
+        "admin.api_key_created",
+        json!({ "api_key_id": api_key.id, "name": api_key.name, "scopes": api_key.scopes }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiKeyResponse { api_key, key }),
+    ))
+}
+
+/// Revokes a key right away.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/api-keys/{id}",
+    tag = "api keys",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(("id" = String, Path, description = "API key id")),
+    responses(
+        (status = 200, description = "Key revoked", body = DeleteApiKeyResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No such key", body = ErrorResponse),
+    )
+)]
+pub async fn delete_api_key(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path(api_key_id): Path<String>,
+) -> Result<Json<DeleteApiKeyResponse>, StatusCode> {
+    let result = sqlx::query("DELETE FROM api_keys WHERE id = $1 AND admin_id = $2")
+        .bind(&api_key_id)
+        .bind(&admin.admin_id)
+        .execute(&pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit::record(
+        &pool,
+        None,
+        Some(&admin.admin_id),
+        "admin.api_key_revoked",
+        json!({ "api_key_id": api_key_id }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(DeleteApiKeyResponse { id: api_key_id }))
+}
@@ -0,0 +1,98 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::AnyPool;
+
+use crate::auth::{require_event_role, ReadEvents, Scoped};
+use crate::models::{AuditEntryResponse, AuditLogQuery, AuditLogResponse, EventRole};
+use crate::request_id::ErrorResponse;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+#[derive(sqlx::FromRow)]
+struct AuditRow {
+    id: i64,
+    action: String,
+    admin_id: Option<String>,
+    details: String,
+    created_at: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/poll/{id}/audit",
+    tag = "polls",
+    security(("session_cookie" = []), ("bearer" = ["events:read"])),
+    params(
+        ("id" = String, Path, description = "Poll id"),
+        AuditLogQuery,
+    ),
+    responses(
+        (status = 200, description = "One page of the poll's audit log", body = AuditLogResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the viewer role on the poll", body = ErrorResponse),
+        (status = 404, description = "No such poll", body = ErrorResponse),
+        (status = 422, description = "`limit` out of range", body = ErrorResponse),
+    )
+)]
+pub async fn get_audit_log(
+    State(pool): State<AnyPool>,
+    Scoped { admin, .. }: Scoped<ReadEvents>,
+    Path(event_id): Path<String>,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<AuditLogResponse>, StatusCode> {
+    require_event_role(&pool, &admin, &event_id, EventRole::Viewer).await?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    // Fetch one extra row to know whether another page follows
+    let mut rows = sqlx::query_as::<_, AuditRow>(
+        r#"
+        SELECT id, action, admin_id, details, created_at
+        FROM audit_log
+        WHERE event_id = $1
+          AND (CAST($2 AS BIGINT) IS NULL OR id < $2)
+        ORDER BY id DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(&event_id)
+    .bind(query.before)
+    .bind(limit + 1)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch audit log");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    let next_cursor = if has_more {
+        rows.last().map(|row| row.id)
+    } else {
+        None
+    };
+
+    let entries = rows
+        .into_iter()
+        .map(|row| AuditEntryResponse {
+            id: row.id,
+            action: row.action,
+            admin_id: row.admin_id,
+            details: serde_json::from_str(&row.details).unwrap_or(serde_json::Value::Null),
+            created_at: row.created_at,
+        })
+        .collect();
+
+    Ok(Json(AuditLogResponse {
+        entries,
+        next_cursor,
+    }))
+}
@@ -0,0 +1,250 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use sqlx::{AnyConnection, AnyPool};
+
+use crate::audit;
+use crate::auth::{require_event_role, AdminContext, ReadEvents, Scoped};
+use crate::db::Backend;
+use crate::models::{AddCollaboratorRequest, CollaboratorResponse, EventRole, OrgRole};
+use crate::request_id::ErrorResponse;
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct RemoveCollaboratorResponse {
+    pub admin_id: String,
+}
+
+/// Number of owners an event would keep if `admin_id` stopped being one as a
+/// collaborator. Admins and owners of the event's organization own it as well,
+/// as in the `event_access` view, and no collaborator change takes that away.
+async fn remaining_owners(
+    conn: &mut AnyConnection,
+    event_id: &str,
+    admin_id: &str,
+) -> Result<i64, sqlx::Error> {
+    // Lock the owner rows so two concurrent demotions can't both pass the check
+    let sql = format!(
+        "SELECT admin_id FROM event_collaborators WHERE event_id = $1 AND role = $2 {}",
+        Backend::of(conn).for_update(),
+    );
+    let owners: Vec<String> = sqlx::query_scalar(&sql)
+        .bind(event_id)
+        .bind(EventRole::Owner)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let organization_owners: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM events e
+        JOIN organization_members m ON m.organization_id = e.organization_id
+        WHERE e.id = $1 AND m.role <> $2
+        "#,
+    )
+    .bind(event_id)
+    .bind(OrgRole::Member)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(owners.iter().filter(|owner| *owner != admin_id).count() as i64 + organization_owners)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/poll/{id}/collaborators",
+    tag = "collaborators",
+    security(("session_cookie" = []), ("bearer" = ["events:read"])),
+    params(("id" = String, Path, description = "Poll id")),
+    responses(
+        (status = 200, description = "Admins with access to the poll", body = Vec<CollaboratorResponse>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the viewer role on the poll", body = ErrorResponse),
+        (status = 404, description = "No such poll", body = ErrorResponse),
+    )
+)]
+pub async fn list_collaborators(
+    State(pool): State<AnyPool>,
+    Scoped { admin, .. }: Scoped<ReadEvents>,
+    Path(event_id): Path<String>,
+) -> Result<Json<Vec<CollaboratorResponse>>, StatusCode> {
+    require_event_role(&pool, &admin, &event_id, EventRole::Viewer).await?;
+
+    let collaborators = sqlx::query_as::<_, CollaboratorResponse>(
+        r#"
+        SELECT c.admin_id, a.name, c.role, c.created_at
+        FROM event_collaborators c
+        JOIN admins a ON a.id = c.admin_id
+        WHERE c.event_id = $1
+        ORDER BY c.created_at ASC
+        "#,
+    )
+    .bind(&event_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch collaborators");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(collaborators))
+}
+
+/// Adds an admin to the event, or changes their role if they already are a collaborator.
+#[utoipa::path(
+    post,
+    path = "/api/v1/poll/{id}/collaborators",
+    tag = "collaborators",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(("id" = String, Path, description = "Poll id")),
+    request_body = AddCollaboratorRequest,
+    responses(
+        (status = 200, description = "The collaborator with their new role", body = CollaboratorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the owner role on the poll", body = ErrorResponse),
+        (status = 404, description = "No such poll", body = ErrorResponse),
+        (status = 409, description = "Would leave the poll without an owner", body = ErrorResponse),
+        (status = 422, description = "No admin has that name", body = ErrorResponse),
+    )
+)]
+pub async fn add_collaborator(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path(event_id): Path<String>,
+    Json(payload): Json<AddCollaboratorRequest>,
+) -> Result<Json<CollaboratorResponse>, StatusCode> {
+    require_event_role(&pool, &admin, &event_id, EventRole::Owner).await?;
+
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let invitee_id: String = sqlx::query_scalar("SELECT id FROM admins WHERE name = $1")
+        .bind(name)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if payload.role != EventRole::Owner {
+        let owners = remaining_owners(&mut tx, &event_id, &invitee_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if owners == 0 {
+            return Err(StatusCode::CONFLICT);
+        }
+    }
+
+    let collaborator = sqlx::query_as::<_, CollaboratorResponse>(
+        r#"
+        INSERT INTO event_collaborators (event_id, admin_id, role, created_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (event_id, admin_id) DO UPDATE SET role = excluded.role
+        RETURNING admin_id, $5 AS name, role, created_at
+        "#,
+    )
+    .bind(&event_id)
+    .bind(&invitee_id)
+    .bind(payload.role)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .bind(name)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to upsert collaborator");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    audit::record(
+        &mut *tx,
+        Some(&event_id),
+        Some(&admin.admin_id),
+        "collaborator.added",
+        json!({ "admin_id": invitee_id, "name": name, "role": payload.role }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(collaborator))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/poll/{id}/collaborators/{admin_id}",
+    tag = "collaborators",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(
+        ("id" = String, Path, description = "Poll id"),
+        ("admin_id" = String, Path, description = "Collaborator's admin id"),
+    ),
+    responses(
+        (status = 200, description = "Collaborator removed", body = RemoveCollaboratorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the owner role on the poll", body = ErrorResponse),
+        (status = 404, description = "No such poll or collaborator", body = ErrorResponse),
+        (status = 409, description = "Would leave the poll without an owner", body = ErrorResponse),
+    )
+)]
+pub async fn remove_collaborator(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path((event_id, collaborator_id)): Path<(String, String)>,
+) -> Result<Json<RemoveCollaboratorResponse>, StatusCode> {
+    require_event_role(&pool, &admin, &event_id, EventRole::Owner).await?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Every event keeps at least one owner
+    let owners = remaining_owners(&mut tx, &event_id, &collaborator_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if owners == 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let role: Option<EventRole> = sqlx::query_scalar(
+        "DELETE FROM event_collaborators WHERE event_id = $1 AND admin_id = $2 RETURNING role",
+    )
+    .bind(&event_id)
+    .bind(&collaborator_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let Some(role) = role else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    audit::record(
+        &mut *tx,
+        Some(&event_id),
+        Some(&admin.admin_id),
+        "collaborator.removed",
+        json!({ "admin_id": collaborator_id, "role": role }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(RemoveCollaboratorResponse {
+        admin_id: collaborator_id,
+    }))
+}
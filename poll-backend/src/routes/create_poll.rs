@@ -1,21 +1,141 @@
-use axum::{extract::State, http::HeaderMap, http::StatusCode, Json};
-use sqlx::PgPool;
+use axum::{extract::State, http::StatusCode, Json};
+use serde_json::{json, Value};
+use sqlx::{AnyConnection, AnyPool};
 
-use crate::auth::require_admin;
-use crate::models::{CreateEventRequest, EventRow, TimeSlotRow};
+use crate::audit;
+use crate::auth::{require_org_role, CreatePolls, Scoped};
+use crate::db::{pg_array, Backend};
+use crate::metrics;
+use crate::models::{CreateEventRequest, EventRole, EventRow, OrgRole, TimeSlotInput, TimeSlotRow};
+use crate::request_id::ErrorResponse;
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, utoipa::ToSchema)]
 pub struct CreateEventResponse {
     pub id: String,
 }
 
+/// Inserts an event with its time slots and records the creation in the audit
+/// log. `source` describes where the poll was copied from, if anywhere.
+pub async fn insert_event(
+    conn: &mut AnyConnection,
+    event: &EventRow,
+    time_slots: &[TimeSlotInput],
+    source: Option<Value>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO events (id, title, description, created_at, updated_at, admin_id, organization_id)
+        VALUES ($1, $2, $3, $4, $4, $5, $6)
+        "#,
+    )
+        .bind(&event.id)
+        .bind(&event.title)
+        .bind(&event.description)
+        .bind(&event.created_at)
+        .bind(&event.admin_id)
+        .bind(&event.organization_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO event_collaborators (event_id, admin_id, role, created_at) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(&event.id)
+    .bind(&event.admin_id)
+    .bind(EventRole::Owner)
+    .bind(&event.created_at)
+    .execute(&mut *conn)
+    .await?;
+
+    insert_time_slots(conn, &event.id, time_slots).await?;
+
+    let mut details = json!({
+        "title": event.title,
+        "description": event.description,
+        "slot_count": time_slots.len(),
+        "organization_id": event.organization_id,
+    });
+    if let Some(source) = source {
+        details["source"] = source;
+    }
+
+    audit::record(
+        &mut *conn,
+        Some(&event.id),
+        Some(&event.admin_id),
+        "event.created",
+        details,
+    )
+    .await
+}
+
+/// Inserts every slot with a single statement.
+async fn insert_time_slots(
+    conn: &mut AnyConnection,
+    event_id: &str,
+    time_slots: &[TimeSlotInput],
+) -> Result<(), sqlx::Error> {
+    if time_slots.is_empty() {
+        return Ok(());
+    }
+
+    let slots: Vec<TimeSlotRow> = time_slots
+        .iter()
+        .map(|slot| TimeSlotRow::new(event_id, slot.starts_at, slot.ends_at))
+        .collect();
+
+    let query = match Backend::of(conn) {
+        Backend::Postgres => sqlx::query(
+            r#"
+            INSERT INTO time_slots (id, event_id, starts_at, ends_at)
+            SELECT s.id, $1, s.starts_at, s.ends_at
+            FROM UNNEST($2::text[], $3::text[], $4::text[]) AS s(id, starts_at, ends_at)
+            "#,
+        )
+        .bind(event_id)
+        .bind(pg_array(slots.iter().map(|slot| &slot.id)))
+        .bind(pg_array(slots.iter().map(|slot| &slot.starts_at)))
+        .bind(pg_array(slots.iter().map(|slot| &slot.ends_at))),
+        Backend::Sqlite => sqlx::query(
+            r#"
+            INSERT INTO time_slots (id, event_id, starts_at, ends_at)
+            SELECT s.value ->> 0, $1, s.value ->> 1, s.value ->> 2
+            FROM json_each($2) AS s
+            "#,
+        )
+        .bind(event_id)
+        .bind(
+            json!(slots
+                .iter()
+                .map(|slot| (&slot.id, &slot.starts_at, &slot.ends_at))
+                .collect::<Vec<_>>())
+            .to_string(),
+        ),
+    };
+    query.execute(conn).await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/poll",
+    tag = "polls",
+    security(("session_cookie" = []), ("bearer" = ["polls:create"])),
+    request_body = CreateEventRequest,
+    responses(
+        (status = 201, description = "Poll created", body = CreateEventResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not a member of the organization", body = ErrorResponse),
+        (status = 404, description = "No such organization", body = ErrorResponse),
+        (status = 422, description = "Empty title, no slots, or a slot that doesn't end after it starts", body = ErrorResponse),
+    )
+)]
 pub async fn create_poll(
-    State(pool): State<PgPool>,
-    headers: HeaderMap,
+    State(pool): State<AnyPool>,
+    Scoped { admin, .. }: Scoped<CreatePolls>,
     Json(payload): Json<CreateEventRequest>,
 ) -> Result<(StatusCode, Json<CreateEventResponse>), StatusCode> {
-    let admin = require_admin(&pool, &headers).await?;
-
     if payload.title.trim().is_empty() {
         return Err(StatusCode::UNPROCESSABLE_ENTITY);
     }
@@ -24,7 +144,24 @@ pub async fn create_poll(
         return Err(StatusCode::UNPROCESSABLE_ENTITY);
     }
 
-    let event = EventRow::new(payload.title, payload.description, admin.admin_id);
+    if !payload
+        .time_slots
+        .iter()
+        .all(TimeSlotInput::ends_after_start)
+    {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    if let Some(organization_id) = &payload.organization_id {
+        require_org_role(&pool, &admin, organization_id, OrgRole::Member).await?;
+    }
+
+    let event = EventRow::new(
+        payload.title,
+        payload.description,
+        admin.admin_id,
+        payload.organization_id,
+    );
 
     // Use a transaction so event + slots are created atomically
     let mut tx = pool
@@ -32,37 +169,16 @@ pub async fn create_poll(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    sqlx::query(
-        "INSERT INTO events (id, title, description, created_at, admin_id) VALUES ($1, $2, $3, $4, $5)",
-    )
-        .bind(&event.id)
-        .bind(&event.title)
-        .bind(&event.description)
-        .bind(&event.created_at)
-        .bind(&event.admin_id)
-    .execute(&mut *tx)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    for slot_input in payload.time_slots {
-        let slot = TimeSlotRow::new(&event.id, slot_input.starts_at, slot_input.ends_at);
-
-        sqlx::query(
-            "INSERT INTO time_slots (id, event_id, starts_at, ends_at) VALUES ($1, $2, $3, $4)",
-        )
-            .bind(&slot.id)
-            .bind(&slot.event_id)
-            .bind(&slot.starts_at)
-            .bind(&slot.ends_at)
-        .execute(&mut *tx)
+    insert_event(&mut tx, &event, &payload.time_slots, None)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    }
 
     tx.commit()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    metrics::poll_created();
+
     Ok((
         StatusCode::CREATED,
         Json(CreateEventResponse { id: event.id }),
@@ -1,45 +1,93 @@
-use axum::{extract::Path, extract::State, http::HeaderMap, http::StatusCode, Json};
-use sqlx::PgPool;
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use serde_json::json;
+use sqlx::AnyPool;
 
-use crate::auth::require_admin;
+use crate::audit;
+use crate::auth::{require_event_role, AdminContext};
+use crate::metrics;
+use crate::models::{EventRole, WebhookEventType};
+use crate::request_id::ErrorResponse;
+use crate::revision;
+use crate::webhooks;
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, utoipa::ToSchema)]
 pub struct DeletePollResponse {
     pub id: String,
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/poll/{id}",
+    tag = "polls",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(("id" = String, Path, description = "Poll id")),
+    responses(
+        (status = 200, description = "Poll moved to the trash", body = DeletePollResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the owner role on the poll", body = ErrorResponse),
+        (status = 404, description = "No such poll", body = ErrorResponse),
+    )
+)]
 pub async fn delete_poll(
-    State(pool): State<PgPool>,
-    headers: HeaderMap,
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
     Path(id): Path<String>,
 ) -> Result<Json<DeletePollResponse>, StatusCode> {
-    let admin = require_admin(&pool, &headers).await?;
+    require_event_role(&pool, &admin, &id, EventRole::Owner).await?;
 
-    let event_admin_id: Option<String> =
-        sqlx::query_scalar("SELECT admin_id FROM events WHERE id = $1")
-            .bind(&id)
-            .fetch_optional(&pool)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let Some(event_admin_id) = event_admin_id else {
+    // Only tombstone the event here; the purge task removes it for good once
+    // the retention period has passed, which keeps it restorable until then.
+    let title: Option<String> = sqlx::query_scalar(
+        r#"
+        UPDATE events
+        SET deleted_at = $2
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING title
+        "#,
+    )
+    .bind(&id)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let Some(title) = title else {
         return Err(StatusCode::NOT_FOUND);
     };
 
-    if event_admin_id != admin.admin_id {
-        return Err(StatusCode::FORBIDDEN);
-    }
+    revision::bump(&mut *tx, &id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &mut *tx,
+        Some(&id),
+        Some(&admin.admin_id),
+        "event.deleted",
+        json!({ "title": title }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let result = sqlx::query("DELETE FROM events WHERE id = $1 AND admin_id = $2")
-        .bind(&id)
-        .bind(&admin.admin_id)
-        .execute(&pool)
+    webhooks::enqueue(
+        &mut tx,
+        &id,
+        WebhookEventType::PollDeleted,
+        json!({ "title": title }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    if result.rows_affected() == 0 {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    metrics::poll_deleted();
 
     Ok(Json(DeletePollResponse { id }))
 }
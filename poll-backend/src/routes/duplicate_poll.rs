@@ -0,0 +1,130 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use sqlx::AnyPool;
+
+use crate::auth::{require_event_role, require_org_role, CreatePolls, Scoped};
+use crate::metrics;
+use crate::models::{
+    parse_timestamp, DuplicatePollRequest, EventRole, EventRow, OrgRole, TimeSlotInput,
+};
+use crate::request_id::ErrorResponse;
+use crate::routes::create_poll::{insert_event, CreateEventResponse};
+
+#[derive(sqlx::FromRow)]
+struct SlotTimesRow {
+    starts_at: String,
+    ends_at: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/poll/{id}/duplicate",
+    tag = "polls",
+    security(("session_cookie" = []), ("bearer" = ["polls:create"])),
+    params(("id" = String, Path, description = "Poll id")),
+    request_body = DuplicatePollRequest,
+    responses(
+        (status = 201, description = "The copy", body = CreateEventResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the viewer role on the poll", body = ErrorResponse),
+        (status = 404, description = "No such poll", body = ErrorResponse),
+        (status = 422, description = "Empty title, an offset out of range, or a slot that doesn't end after it starts", body = ErrorResponse),
+    )
+)]
+pub async fn duplicate_poll(
+    State(pool): State<AnyPool>,
+    Scoped { admin, .. }: Scoped<CreatePolls>,
+    Path(event_id): Path<String>,
+    Json(payload): Json<DuplicatePollRequest>,
+) -> Result<(StatusCode, Json<CreateEventResponse>), StatusCode> {
+    require_event_role(&pool, &admin, &event_id, EventRole::Viewer).await?;
+
+    let source = sqlx::query_as::<_, EventRow>(
+        "SELECT id, title, description, created_at, admin_id, organization_id FROM events WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(&event_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch event");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let title = match payload.title {
+        Some(title) if title.trim().is_empty() => return Err(StatusCode::UNPROCESSABLE_ENTITY),
+        Some(title) => title,
+        None => source.title,
+    };
+
+    let slots = sqlx::query_as::<_, SlotTimesRow>(
+        "SELECT starts_at, ends_at FROM time_slots WHERE event_id = $1 ORDER BY starts_at ASC",
+    )
+    .bind(&event_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch time slots");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let offset = chrono::Duration::try_minutes(payload.offset_minutes)
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let time_slots = slots
+        .iter()
+        .map(|slot| {
+            let starts_at = parse_timestamp(&slot.starts_at)?.checked_add_signed(offset)?;
+            let ends_at = parse_timestamp(&slot.ends_at)?.checked_add_signed(offset)?;
+            Some(TimeSlotInput { starts_at, ends_at }).filter(TimeSlotInput::ends_after_start)
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    // The copy stays with the organization only if the caller belongs to it;
+    // collaborators from outside get a personal copy.
+    let organization_id = match source.organization_id {
+        Some(organization_id) => require_org_role(&pool, &admin, &organization_id, OrgRole::Member)
+            .await
+            .ok()
+            .map(|_| organization_id),
+        None => None,
+    };
+
+    let event = EventRow::new(title, source.description, admin.admin_id, organization_id);
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    insert_event(
+        &mut tx,
+        &event,
+        &time_slots,
+        Some(json!({
+            "duplicated_from": event_id,
+            "offset_minutes": payload.offset_minutes,
+        })),
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to insert duplicated event");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    metrics::poll_created();
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateEventResponse { id: event.id }),
+    ))
+}
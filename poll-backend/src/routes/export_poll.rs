@@ -0,0 +1,119 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use sqlx::AnyPool;
+
+use crate::auth::{require_event_role, ExportPolls, Scoped};
+use crate::models::{EventRole, ParticipantResponse, TimeSlotResponse};
+use crate::request_id::ErrorResponse;
+use crate::routes::get_poll::{fetch_participants, fetch_slot_counts};
+
+/// The answers as CSV: one column per slot, one row per participant with
+/// `yes`, `no` or nothing, and a last row with the number available.
+#[utoipa::path(
+    get,
+    path = "/api/v1/poll/{id}/export",
+    tag = "polls",
+    security(("session_cookie" = []), ("bearer" = ["polls:export"])),
+    params(("id" = String, Path, description = "Poll id")),
+    responses(
+        (status = 200, description = "The answers as CSV", body = String, content_type = "text/csv"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the viewer role on the poll", body = ErrorResponse),
+        (status = 404, description = "No such poll", body = ErrorResponse),
+    )
+)]
+pub async fn export_poll(
+    State(pool): State<AnyPool>,
+    Scoped { admin, .. }: Scoped<ExportPolls>,
+    Path(event_id): Path<String>,
+) -> Result<Response, StatusCode> {
+    require_event_role(&pool, &admin, &event_id, EventRole::Viewer).await?;
+
+    let time_slots = fetch_slot_counts(&pool, &event_id).await.map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch time slots");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let participants = fetch_participants(&pool, &event_id).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"poll-{event_id}.csv\""),
+            ),
+        ],
+        to_csv(&time_slots, &participants),
+    )
+        .into_response())
+}
+
+fn to_csv(time_slots: &[TimeSlotResponse], participants: &[ParticipantResponse]) -> String {
+    let mut rows = Vec::with_capacity(participants.len() + 2);
+
+    let mut header = vec!["participant".to_string()];
+    header.extend(
+        time_slots
+            .iter()
+            .map(|slot| format!("{}/{}", slot.starts_at, slot.ends_at)),
+    );
+    rows.push(header);
+
+    for participant in participants {
+        let mut row = vec![csv_field(&participant.name)];
+        row.extend(time_slots.iter().map(|slot| {
+            let vote = participant
+                .votes
+                .iter()
+                .find(|vote| vote.time_slot_id == slot.id);
+            match vote {
+                Some(vote) if vote.available => "yes".to_string(),
+                Some(_) => "no".to_string(),
+                None => String::new(),
+            }
+        }));
+        rows.push(row);
+    }
+
+    let mut totals = vec!["available".to_string()];
+    totals.extend(
+        time_slots
+            .iter()
+            .map(|slot| slot.available_count.to_string()),
+    );
+    rows.push(totals);
+
+    rows.iter().map(|row| row.join(",") + "\r\n").collect()
+}
+
+/// Quotes a participant-chosen value as RFC 4180 asks, and keeps
+/// spreadsheets from reading it as a formula.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_and_defuses_names() {
+        assert_eq!(csv_field("Jo"), "Jo");
+        assert_eq!(csv_field("Smith, Jo"), "\"Smith, Jo\"");
+        assert_eq!(csv_field("Jo \"JJ\""), "\"Jo \"\"JJ\"\"\"");
+        assert_eq!(csv_field("=SUM(A1)"), "'=SUM(A1)");
+        assert_eq!(csv_field("=1,2"), "\"'=1,2\"");
+    }
+}
@@ -1,11 +1,29 @@
+use std::sync::Arc;
+
 use axum::{
+    body::Bytes,
     extract::{Path, State},
-    http::StatusCode,
-    Json,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
 };
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{AnyExecutor, AnyPool};
+
+use crate::api_version::http_date;
+use crate::metrics;
+use crate::models::{ParticipantResponse, PollResponse, TimeSlotResponse, VoteResponse};
+use crate::request_id::ErrorResponse;
+use crate::revision::PollCache;
 
-use crate::models::{EventRow, ParticipantResponse, PollResponse, TimeSlotResponse, VoteResponse};
+#[derive(sqlx::FromRow)]
+struct PollEventRow {
+    id: String,
+    title: String,
+    description: Option<String>,
+    created_at: String,
+    revision: i64,
+    updated_at: Option<String>,
+}
 
 #[derive(sqlx::FromRow)]
 struct TimeSlotWithCount {
@@ -28,24 +46,12 @@ struct VoteRow {
     available: i32,
 }
 
-pub async fn get_poll(
-    State(pool): State<PgPool>,
-    Path(event_id): Path<String>,
-) -> Result<Json<PollResponse>, StatusCode> {
-    // Fetch the event
-    let event = sqlx::query_as::<_, EventRow>(
-        "SELECT id, title, description, created_at, admin_id FROM events WHERE id = $1",
-    )
-    .bind(&event_id)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| {
-        tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch event");
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?
-    .ok_or(StatusCode::NOT_FOUND)?;
-
-    // Fetch time slots with vote counts
+/// Time slots of an event with the number of participants available for each,
+/// ordered by start time.
+pub async fn fetch_slot_counts<'e>(
+    executor: impl AnyExecutor<'e>,
+    event_id: &str,
+) -> Result<Vec<TimeSlotResponse>, sqlx::Error> {
     let slots = sqlx::query_as::<_, TimeSlotWithCount>(
         r#"
         SELECT
@@ -60,20 +66,147 @@ pub async fn get_poll(
         ORDER BY ts.starts_at ASC
         "#,
     )
+    .bind(event_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(slots
+        .into_iter()
+        .map(|s| TimeSlotResponse {
+            id: s.id,
+            starts_at: s.starts_at,
+            ends_at: s.ends_at,
+            available_count: s.available_count,
+        })
+        .collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/poll/{id}",
+    tag = "votes",
+    params(
+        ("id" = String, Path, description = "Poll id"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a copy the client already has"),
+    ),
+    responses(
+        (status = 200, description = "The poll with its slots and answers", body = PollResponse,
+            headers(
+                ("ETag" = String, description = "Poll revision; changes with every write to the poll"),
+                ("Last-Modified" = String, description = "When the poll last changed"),
+            )),
+        (status = 304, description = "The poll hasn't changed since the given ETag"),
+        (status = 404, description = "No such poll", body = ErrorResponse),
+    )
+)]
+pub async fn get_poll(
+    State(pool): State<AnyPool>,
+    State(cache): State<Arc<PollCache>>,
+    Path(event_id): Path<String>,
+    request_headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    // The revision is read before the rest of the poll, so the content sent
+    // is never older than the ETag it is labelled with
+    let event = sqlx::query_as::<_, PollEventRow>(
+        "SELECT id, title, description, created_at, revision, updated_at FROM events WHERE id = $1 AND deleted_at IS NULL",
+    )
     .bind(&event_id)
-    .fetch_all(&pool)
+    .fetch_optional(&pool)
     .await
     .map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch event");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let etag = format!("\"{}\"", event.revision);
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, value);
+    }
+    let updated_at = event.updated_at.as_deref().unwrap_or(&event.created_at);
+    if let Ok(updated_at) = DateTime::parse_from_rfc3339(updated_at) {
+        if let Ok(value) = HeaderValue::from_str(&http_date(updated_at.with_timezone(&Utc))) {
+            headers.insert(header::LAST_MODIFIED, value);
+        }
+    }
+
+    if matches_etag(&request_headers, &etag) {
+        metrics::poll_read("not_modified");
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    let body = match cache.get(&event.id, event.revision) {
+        Some(body) => {
+            metrics::poll_read("cache_hit");
+            body
+        }
+        None => {
+            metrics::poll_read("cache_miss");
+            let revision = event.revision;
+            let poll = fetch_poll(&pool, event).await?;
+            let body = Bytes::from(serde_json::to_vec(&poll).map_err(|e| {
+                tracing::error!(error = ?e, event_id = %event_id, "Failed to serialize poll");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?);
+            cache.insert(&event_id, revision, body.clone());
+            body
+        }
+    };
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Ok((headers, body).into_response())
+}
+
+/// Whether `If-None-Match` lists `etag` or `*`. Weak tags match too, as
+/// RFC 9110 asks for this header.
+fn matches_etag(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+/// The slots, participants and answers of `event`.
+async fn fetch_poll(pool: &AnyPool, event: PollEventRow) -> Result<PollResponse, StatusCode> {
+    let event_id = event.id.as_str();
+
+    // Fetch time slots with vote counts
+    let time_slots = fetch_slot_counts(pool, event_id).await.map_err(|e| {
         tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch time slots");
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    let participants = fetch_participants(pool, event_id).await?;
+
+    Ok(PollResponse {
+        id: event.id,
+        title: event.title,
+        description: event.description,
+        created_at: event.created_at,
+        time_slots,
+        participants,
+    })
+}
+
+/// Participants of an event with their answers, in the order they joined.
+pub async fn fetch_participants(
+    pool: &AnyPool,
+    event_id: &str,
+) -> Result<Vec<ParticipantResponse>, StatusCode> {
     // Fetch participants
     let participants = sqlx::query_as::<_, ParticipantNameRow>(
         "SELECT id, name FROM participants WHERE event_id = $1 ORDER BY created_at ASC",
     )
-    .bind(&event_id)
-    .fetch_all(&pool)
+    .bind(event_id)
+    .fetch_all(pool)
     .await
     .map_err(|e| {
         tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch participants");
@@ -92,25 +225,15 @@ pub async fn get_poll(
         WHERE ts.event_id = $1
         "#,
     )
-    .bind(&event_id)
-    .fetch_all(&pool)
+    .bind(event_id)
+    .fetch_all(pool)
     .await
     .map_err(|e| {
         tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch votes");
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    let time_slots = slots
-        .into_iter()
-        .map(|s| TimeSlotResponse {
-            id: s.id,
-            starts_at: s.starts_at,
-            ends_at: s.ends_at,
-            available_count: s.available_count,
-        })
-        .collect();
-
-    let participants = participants
+    Ok(participants
         .into_iter()
         .map(|p| {
             let participant_votes = votes
@@ -128,14 +251,26 @@ pub async fn get_poll(
                 votes: participant_votes,
             }
         })
-        .collect();
+        .collect())
+}
 
-    Ok(Json(PollResponse {
-        id: event.id,
-        title: event.title,
-        description: event.description,
-        created_at: event.created_at,
-        time_slots,
-        participants,
-    }))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn if_none_match(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn matches_listed_weak_and_wildcard_etags() {
+        assert!(matches_etag(&if_none_match("\"3\""), "\"3\""));
+        assert!(matches_etag(&if_none_match("\"1\", W/\"3\""), "\"3\""));
+        assert!(matches_etag(&if_none_match("*"), "\"3\""));
+        assert!(!matches_etag(&if_none_match("\"2\""), "\"3\""));
+        assert!(!matches_etag(&if_none_match("3"), "\"3\""));
+        assert!(!matches_etag(&HeaderMap::new(), "\"3\""));
+    }
 }
@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use sqlx::AnyPool;
+
+use crate::db::Backend;
+use crate::metrics;
+use crate::state::AppState;
+
+/// Readiness fails rather than hang when the database doesn't answer in time.
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub database: bool,
+    pub migrations: bool,
+}
+
+/// Liveness: the process is up and serving. Deliberately ignores the
+/// database, so an outage doesn't get every instance restarted.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses(
+        (status = 200, description = "The process is up", body = String, content_type = "text/plain"),
+    )
+)]
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Readiness: the pool hands out working connections and every bundled
+/// migration has been applied.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready to serve", body = ReadinessResponse),
+        (status = 503, description = "The database is unreachable or behind on migrations", body = ReadinessResponse),
+    )
+)]
+pub async fn readyz(State(pool): State<AnyPool>) -> (StatusCode, Json<ReadinessResponse>) {
+    let applied = tokio::time::timeout(READINESS_TIMEOUT, applied_migrations(&pool))
+        .await
+        .ok()
+        .and_then(Result::ok);
+
+    let database = applied.is_some();
+    let migrations = applied.is_some_and(|applied| {
+        Backend::of_pool(&pool)
+            .migrator()
+            .iter()
+            .all(|migration| applied.contains(&migration.version))
+    });
+
+    let ready = database && migrations;
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(ReadinessResponse {
+            ready,
+            database,
+            migrations,
+        }),
+    )
+}
+
+async fn applied_migrations(pool: &AnyPool) -> Result<HashSet<i64>, sqlx::Error> {
+    let versions: Vec<i64> =
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(pool)
+            .await?;
+    Ok(versions.into_iter().collect())
+}
+
+/// Prometheus text format.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain"),
+    )
+)]
+pub async fn prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    metrics::record_pool(&state.pool);
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+}
@@ -1,22 +1,198 @@
-use axum::{extract::State, http::HeaderMap, http::StatusCode, Json};
-use sqlx::PgPool;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sqlx::AnyPool;
 
-use crate::auth::require_admin;
-use crate::models::EventSummaryResponse;
+use crate::auth::{ReadEvents, Scoped};
+use crate::db::Backend;
+use crate::models::{
+    EventListResponse, EventScope, EventStatusFilter, EventSummaryResponse, ListEventsQuery,
+};
+use crate::request_id::{ErrorReason, ErrorResponse};
 
-pub async fn list_events(
-    State(pool): State<PgPool>,
-    headers: HeaderMap,
-) -> Result<Json<Vec<EventSummaryResponse>>, StatusCode> {
-    let admin = require_admin(&pool, &headers).await?;
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// Position of the last event on a page: `(created_at, id)`.
+struct Cursor {
+    created_at: String,
+    id: String,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}\n{}", self.created_at, self.id))
+    }
+
+    fn decode(raw: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(raw).ok()?;
+        let text = String::from_utf8(bytes).ok()?;
+        let (created_at, id) = text.split_once('\n')?;
+        Some(Self {
+            created_at: created_at.to_string(),
+            id: id.to_string(),
+        })
+    }
+}
+
+/// Escapes LIKE wildcards so the search term is matched literally.
+fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
 
-    let events = sqlx::query_as::<_, EventSummaryResponse>(
-        "SELECT id, title, description, created_at FROM events WHERE admin_id = $1 ORDER BY created_at DESC",
+#[utoipa::path(
+    get,
+    path = "/api/v1/events",
+    tag = "polls",
+    security(("session_cookie" = []), ("bearer" = ["events:read"])),
+    params(ListEventsQuery),
+    responses(
+        (status = 200, description = "One page of polls the admin can access", body = EventListResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 422, description = "Bad cursor, `limit` out of range, `scope=organization` without `organization_id`, or `status=finalized`, which isn't supported yet", body = ErrorResponse),
     )
-    .bind(&admin.admin_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+)]
+pub async fn list_events(
+    State(pool): State<AnyPool>,
+    Scoped { admin, .. }: Scoped<ReadEvents>,
+    Query(query): Query<ListEventsQuery>,
+) -> Result<Response, StatusCode> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let cursor = match query.cursor.as_deref() {
+        Some(raw) => Some(Cursor::decode(raw).ok_or(StatusCode::UNPROCESSABLE_ENTITY)?),
+        None => None,
+    };
+
+    let search = query
+        .q
+        .as_deref()
+        .map(str::trim)
+        .filter(|term| !term.is_empty())
+        .map(like_pattern);
+
+    let scope = match query.scope {
+        EventScope::All => "all",
+        EventScope::Personal => "personal",
+        EventScope::Organization if query.organization_id.is_none() => {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY)
+        }
+        EventScope::Organization => "organization",
+    };
+
+    let status = match query.status {
+        None => None,
+        Some(EventStatusFilter::Open) => Some("open"),
+        Some(EventStatusFilter::Closed) => Some("closed"),
+        Some(EventStatusFilter::Finalized) => {
+            let reason =
+                ErrorReason("status=finalized is not supported: polls have no finalized state yet");
+            return Ok((StatusCode::UNPROCESSABLE_ENTITY, Extension(reason)).into_response());
+        }
+    };
+
+    // Fetch one extra row to know whether another page follows
+    let backend = Backend::of_pool(&pool);
+    let starts_at = backend.timestamp("ts.starts_at");
+    let sql = format!(
+        r#"
+        SELECT *
+        FROM (
+            SELECT
+                e.id,
+                e.title,
+                e.description,
+                e.created_at,
+                e.organization_id,
+                e.deleted_at,
+                a.role,
+                (SELECT COUNT(*) FROM participants p WHERE p.event_id = e.id) AS participant_count,
+                (
+                    SELECT ts.starts_at
+                    FROM time_slots ts
+                    WHERE ts.event_id = e.id AND {starts_at} > {now}
+                    ORDER BY {starts_at} ASC
+                    LIMIT 1
+                ) AS next_slot_starts_at
+            FROM events e
+            JOIN event_access a ON a.event_id = e.id AND a.admin_id = $1
+            WHERE (e.deleted_at IS NOT NULL) = $9
+              AND ($10 <> 'personal' OR e.organization_id IS NULL)
+              AND ($10 <> 'organization' OR e.organization_id = $11)
+              AND (
+                  CAST($2 AS TEXT) IS NULL
+                  OR LOWER(e.title) LIKE LOWER($2) ESCAPE '\'
+                  OR LOWER(e.description) LIKE LOWER($2) ESCAPE '\'
+              )
+              AND (
+                  (CAST($3 AS TEXT) IS NULL AND CAST($4 AS TEXT) IS NULL)
+                  OR EXISTS (
+                      SELECT 1
+                      FROM time_slots ts
+                      WHERE ts.event_id = e.id
+                        AND (CAST($3 AS TEXT) IS NULL OR {starts_at} >= {from})
+                        AND (CAST($4 AS TEXT) IS NULL OR {starts_at} < {to})
+                  )
+              )
+              AND (CAST($5 AS TEXT) IS NULL OR (e.created_at, e.id) < ($5, $6))
+        ) summary
+        WHERE CAST($7 AS TEXT) IS NULL
+           OR ($7 = 'open' AND summary.next_slot_starts_at IS NOT NULL)
+           OR ($7 = 'closed' AND summary.next_slot_starts_at IS NULL)
+        ORDER BY summary.created_at DESC, summary.id DESC
+        LIMIT $8
+        "#,
+        now = backend.timestamp("CURRENT_TIMESTAMP"),
+        from = backend.timestamp("$3"),
+        to = backend.timestamp("$4"),
+    );
+    let mut events = sqlx::query_as::<_, EventSummaryResponse>(&sql)
+        .bind(&admin.admin_id)
+        .bind(search)
+        .bind(query.from.map(|from| from.to_rfc3339()))
+        .bind(query.to.map(|to| to.to_rfc3339()))
+        .bind(cursor.as_ref().map(|c| c.created_at.as_str()))
+        .bind(cursor.as_ref().map(|c| c.id.as_str()))
+        .bind(status)
+        .bind(limit + 1)
+        .bind(query.deleted.unwrap_or(false))
+        .bind(scope)
+        .bind(query.organization_id.as_deref())
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = ?e, admin_id = %admin.admin_id, "Failed to list events");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let has_more = events.len() as i64 > limit;
+    events.truncate(limit as usize);
+    let next_cursor = if has_more {
+        events.last().map(|event| {
+            Cursor {
+                created_at: event.created_at.clone(),
+                id: event.id.clone(),
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
 
-    Ok(Json(events))
+    Ok(Json(EventListResponse {
+        events,
+        next_cursor,
+    })
+    .into_response())
 }
@@ -1,7 +1,135 @@
+pub mod admin_account;
 pub mod admin_auth;
+pub mod admin_oidc;
+pub mod api_keys;
+pub mod audit_log;
+pub mod collaborators;
 pub mod create_poll;
 pub mod delete_poll;
+pub mod duplicate_poll;
+pub mod export_poll;
 pub mod get_poll;
+pub mod health;
 pub mod list_events;
+pub mod organizations;
+pub mod remove_participant;
+pub mod restore_poll;
 pub mod submit_vote;
+pub mod templates;
+pub mod two_factor;
 pub mod update_votes;
+pub mod webhooks;
+
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use self::{
+    admin_account::{
+        change_password, confirm_password_reset, delete_account, request_password_reset,
+    },
+    admin_auth::{auth_config, create_invite, login_admin, logout_admin, signup_admin},
+    admin_oidc::{begin_oidc_link, begin_oidc_login, oidc_callback, unlink_oidc},
+    api_keys::{create_api_key, delete_api_key, list_api_keys},
+    audit_log::get_audit_log,
+    collaborators::{add_collaborator, list_collaborators, remove_collaborator},
+    create_poll::create_poll,
+    delete_poll::delete_poll,
+    duplicate_poll::duplicate_poll,
+    export_poll::export_poll,
+    get_poll::get_poll,
+    list_events::list_events,
+    organizations::{
+        add_member, create_organization, list_members, list_organizations, remove_member,
+    },
+    remove_participant::remove_participant,
+    restore_poll::restore_poll,
+    submit_vote::submit_vote,
+    templates::{create_poll_from_template, delete_template, list_templates, save_template},
+    two_factor::{
+        complete_login, disable_totp, enable_totp, get_two_factor_status,
+        regenerate_recovery_codes, setup_totp,
+    },
+    update_votes::update_votes,
+    webhooks::{
+        create_webhook, delete_webhook, list_deliveries, list_webhooks, ping_webhook,
+        retry_delivery,
+    },
+};
+use crate::state::AppState;
+
+/// Every API route, relative to the prefix of the version serving it.
+pub fn api() -> Router<AppState> {
+    Router::new()
+        .route("/admin/signup", post(signup_admin))
+        .route("/admin/login", post(login_admin))
+        .route("/admin/auth-config", get(auth_config))
+        .route("/admin/invites", post(create_invite))
+        .route("/admin/login/2fa", post(complete_login))
+        .route("/admin/logout", post(logout_admin))
+        .route("/admin/password", post(change_password))
+        .route("/admin/password-reset", post(request_password_reset))
+        .route(
+            "/admin/password-reset/confirm",
+            post(confirm_password_reset),
+        )
+        .route("/admin/account", delete(delete_account))
+        .route("/admin/oidc/login", get(begin_oidc_login))
+        .route("/admin/oidc/callback", get(oidc_callback))
+        .route(
+            "/admin/oidc/link",
+            post(begin_oidc_link).delete(unlink_oidc),
+        )
+        .route("/admin/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/admin/api-keys/:id", delete(delete_api_key))
+        .route("/admin/2fa", get(get_two_factor_status))
+        .route("/admin/2fa/setup", post(setup_totp))
+        .route("/admin/2fa/enable", post(enable_totp))
+        .route("/admin/2fa/disable", post(disable_totp))
+        .route("/admin/2fa/recovery-codes", post(regenerate_recovery_codes))
+        .route("/poll", post(create_poll))
+        .route("/poll/:id", get(get_poll).delete(delete_poll))
+        .route("/poll/:id/restore", post(restore_poll))
+        .route("/poll/:id/duplicate", post(duplicate_poll))
+        .route("/poll/:id/export", get(export_poll))
+        .route("/poll/:id/template", post(save_template))
+        .route("/poll/:id/vote", post(submit_vote))
+        .route(
+            "/poll/:id/participant/:participant_id",
+            put(update_votes).delete(remove_participant),
+        )
+        .route("/poll/:id/audit", get(get_audit_log))
+        .route(
+            "/poll/:id/collaborators",
+            get(list_collaborators).post(add_collaborator),
+        )
+        .route(
+            "/poll/:id/collaborators/:admin_id",
+            delete(remove_collaborator),
+        )
+        .route("/events", get(list_events))
+        .route(
+            "/organizations",
+            get(list_organizations).post(create_organization),
+        )
+        .route(
+            "/organizations/:id/members",
+            get(list_members).post(add_member),
+        )
+        .route(
+            "/organizations/:id/members/:admin_id",
+            delete(remove_member),
+        )
+        .route("/templates", get(list_templates))
+        .route("/templates/:id", delete(delete_template))
+        .route("/templates/:id/poll", post(create_poll_from_template))
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route("/webhooks/:id", delete(delete_webhook))
+        .route("/webhooks/:id/ping", post(ping_webhook))
+        .route("/webhooks/:id/deliveries", get(list_deliveries))
+        .route(
+            "/webhooks/:id/deliveries/:delivery_id/retry",
+            post(retry_delivery),
+        )
+}
@@ -0,0 +1,400 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use sqlx::{AnyConnection, AnyPool};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::{require_org_role, AdminContext};
+use crate::db::Backend;
+use crate::models::{
+    AddMemberRequest, CreateOrganizationRequest, MemberResponse, OrgRole, OrganizationResponse,
+};
+use crate::request_id::ErrorResponse;
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct RemoveMemberResponse {
+    pub admin_id: String,
+}
+
+/// Number of owners the organization would keep if `admin_id` stopped being one.
+async fn remaining_owners(
+    conn: &mut AnyConnection,
+    organization_id: &str,
+    admin_id: &str,
+) -> Result<i64, sqlx::Error> {
+    // Lock the owner rows so two concurrent demotions can't both pass the check
+    let sql = format!(
+        "SELECT admin_id FROM organization_members WHERE organization_id = $1 AND role = $2 {}",
+        Backend::of(conn).for_update(),
+    );
+    let owners: Vec<String> = sqlx::query_scalar(&sql)
+        .bind(organization_id)
+        .bind(OrgRole::Owner)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(owners.iter().filter(|owner| *owner != admin_id).count() as i64)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations",
+    tag = "organizations",
+    security(("session_cookie" = []), ("bearer" = [])),
+    request_body = CreateOrganizationRequest,
+    responses(
+        (status = 201, description = "Organization created, with the admin as owner", body = OrganizationResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 409, description = "The name is taken", body = ErrorResponse),
+        (status = 422, description = "Empty name", body = ErrorResponse),
+    )
+)]
+pub async fn create_organization(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Json(payload): Json<CreateOrganizationRequest>,
+) -> Result<(StatusCode, Json<OrganizationResponse>), StatusCode> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let organization = OrganizationResponse {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        role: OrgRole::Owner,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let organization_insert =
+        sqlx::query("INSERT INTO organizations (id, name, created_at) VALUES ($1, $2, $3)")
+            .bind(&organization.id)
+            .bind(&organization.name)
+            .bind(&organization.created_at)
+            .execute(&mut *tx)
+            .await;
+
+    if let Err(err) = organization_insert {
+        if let sqlx::Error::Database(db_err) = &err {
+            if db_err.is_unique_violation() {
+                return Err(StatusCode::CONFLICT);
+            }
+        }
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    sqlx::query(
+        "INSERT INTO organization_members (organization_id, admin_id, role, created_at) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(&organization.id)
+    .bind(&admin.admin_id)
+    .bind(OrgRole::Owner)
+    .bind(&organization.created_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &mut *tx,
+        None,
+        Some(&admin.admin_id),
+        "organization.created",
+        json!({ "organization_id": organization.id, "name": organization.name }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, Json(organization)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations",
+    tag = "organizations",
+    security(("session_cookie" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "Organizations the admin belongs to", body = Vec<OrganizationResponse>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+pub async fn list_organizations(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+) -> Result<Json<Vec<OrganizationResponse>>, StatusCode> {
+    let organizations = sqlx::query_as::<_, OrganizationResponse>(
+        r#"
+        SELECT o.id, o.name, m.role, o.created_at
+        FROM organizations o
+        JOIN organization_members m ON m.organization_id = o.id
+        WHERE m.admin_id = $1
+        ORDER BY o.name ASC
+        "#,
+    )
+    .bind(&admin.admin_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(organizations))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/members",
+    tag = "organizations",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(("id" = String, Path, description = "Organization id")),
+    responses(
+        (status = 200, description = "Members and their roles", body = Vec<MemberResponse>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not a member", body = ErrorResponse),
+        (status = 404, description = "No such organization", body = ErrorResponse),
+    )
+)]
+pub async fn list_members(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path(organization_id): Path<String>,
+) -> Result<Json<Vec<MemberResponse>>, StatusCode> {
+    require_org_role(&pool, &admin, &organization_id, OrgRole::Member).await?;
+
+    let members = sqlx::query_as::<_, MemberResponse>(
+        r#"
+        SELECT m.admin_id, a.name, m.role, m.created_at
+        FROM organization_members m
+        JOIN admins a ON a.id = m.admin_id
+        WHERE m.organization_id = $1
+        ORDER BY m.created_at ASC
+        "#,
+    )
+    .bind(&organization_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(members))
+}
+
+/// Adds an admin to the organization, or changes their role if already a member.
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{id}/members",
+    tag = "organizations",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(("id" = String, Path, description = "Organization id")),
+    request_body = AddMemberRequest,
+    responses(
+        (status = 200, description = "The member with their new role", body = MemberResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the admin role, or the owner role to grant or revoke ownership", body = ErrorResponse),
+        (status = 404, description = "No such organization", body = ErrorResponse),
+        (status = 409, description = "Would leave the organization without an owner", body = ErrorResponse),
+        (status = 422, description = "No admin has that name", body = ErrorResponse),
+    )
+)]
+pub async fn add_member(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path(organization_id): Path<String>,
+    Json(payload): Json<AddMemberRequest>,
+) -> Result<Json<MemberResponse>, StatusCode> {
+    let role = require_org_role(&pool, &admin, &organization_id, OrgRole::Admin).await?;
+
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let member_id: String = sqlx::query_scalar("SELECT id FROM admins WHERE name = $1")
+        .bind(name)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let current: Option<OrgRole> = sqlx::query_scalar(
+        "SELECT role FROM organization_members WHERE organization_id = $1 AND admin_id = $2",
+    )
+    .bind(&organization_id)
+    .bind(&member_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Only owners may hand out or take away ownership
+    if (payload.role == OrgRole::Owner || current == Some(OrgRole::Owner)) && role != OrgRole::Owner
+    {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if current == Some(OrgRole::Owner) && payload.role != OrgRole::Owner {
+        let owners = remaining_owners(&mut tx, &organization_id, &member_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if owners == 0 {
+            return Err(StatusCode::CONFLICT);
+        }
+    }
+
+    let member = sqlx::query_as::<_, MemberResponse>(
+        r#"
+        INSERT INTO organization_members (organization_id, admin_id, role, created_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (organization_id, admin_id) DO UPDATE SET role = excluded.role
+        RETURNING admin_id, $5 AS name, role, created_at
+        "#,
+    )
+    .bind(&organization_id)
+    .bind(&member_id)
+    .bind(payload.role)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .bind(name)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(
+            error = ?e,
+            organization_id = %organization_id,
+            "Failed to upsert organization member"
+        );
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let (action, details) = match current {
+        Some(previous) => (
+            "organization.member_role_changed",
+            json!({
+                "organization_id": organization_id,
+                "admin_id": member_id,
+                "name": name,
+                "previous_role": previous,
+                "role": payload.role,
+            }),
+        ),
+        None => (
+            "organization.member_added",
+            json!({
+                "organization_id": organization_id,
+                "admin_id": member_id,
+                "name": name,
+                "role": payload.role,
+            }),
+        ),
+    };
+    audit::record(&mut *tx, None, Some(&admin.admin_id), action, details)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(member))
+}
+
+/// Removes a member. Admins may remove members and other admins, owners may
+/// remove anyone, and every member may leave on their own.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/members/{admin_id}",
+    tag = "organizations",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(
+        ("id" = String, Path, description = "Organization id"),
+        ("admin_id" = String, Path, description = "Member's admin id"),
+    ),
+    responses(
+        (status = 200, description = "Member removed", body = RemoveMemberResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Role too low to remove that member", body = ErrorResponse),
+        (status = 404, description = "No such organization or member", body = ErrorResponse),
+        (status = 409, description = "Would leave the organization without an owner", body = ErrorResponse),
+    )
+)]
+pub async fn remove_member(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path((organization_id, member_id)): Path<(String, String)>,
+) -> Result<Json<RemoveMemberResponse>, StatusCode> {
+    let role = require_org_role(&pool, &admin, &organization_id, OrgRole::Member).await?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let target: Option<OrgRole> = sqlx::query_scalar(
+        "SELECT role FROM organization_members WHERE organization_id = $1 AND admin_id = $2",
+    )
+    .bind(&organization_id)
+    .bind(&member_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let Some(target) = target else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let leaving = member_id == admin.admin_id;
+    let required = if target == OrgRole::Owner {
+        OrgRole::Owner
+    } else {
+        OrgRole::Admin
+    };
+    if !leaving && role < required {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if target == OrgRole::Owner {
+        let owners = remaining_owners(&mut tx, &organization_id, &member_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if owners == 0 {
+            return Err(StatusCode::CONFLICT);
+        }
+    }
+
+    sqlx::query("DELETE FROM organization_members WHERE organization_id = $1 AND admin_id = $2")
+        .bind(&organization_id)
+        .bind(&member_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &mut *tx,
+        None,
+        Some(&admin.admin_id),
+        "organization.member_removed",
+        json!({ "organization_id": organization_id, "admin_id": member_id, "role": target }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(RemoveMemberResponse {
+        admin_id: member_id,
+    }))
+}
@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use sqlx::AnyPool;
+
+use crate::audit;
+use crate::auth::{require_event_role, AdminContext};
+use crate::models::{EventRole, RemoveParticipantResponse};
+use crate::request_id::ErrorResponse;
+use crate::revision;
+use crate::routes::get_poll::fetch_slot_counts;
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/poll/{id}/participant/{participant_id}",
+    tag = "votes",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(
+        ("id" = String, Path, description = "Poll id"),
+        ("participant_id" = String, Path, description = "Participant id"),
+    ),
+    responses(
+        (status = 200, description = "Participant and their answers removed", body = RemoveParticipantResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the editor role on the poll", body = ErrorResponse),
+        (status = 404, description = "No such poll or participant", body = ErrorResponse),
+    )
+)]
+pub async fn remove_participant(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path((event_id, participant_id)): Path<(String, String)>,
+) -> Result<Json<RemoveParticipantResponse>, StatusCode> {
+    require_event_role(&pool, &admin, &event_id, EventRole::Editor).await?;
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to begin transaction");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Votes go with the participant through ON DELETE CASCADE
+    let name: Option<String> = sqlx::query_scalar(
+        "DELETE FROM participants WHERE id = $1 AND event_id = $2 RETURNING name",
+    )
+    .bind(&participant_id)
+    .bind(&event_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(
+            error = ?e,
+            event_id = %event_id,
+            participant_id = %participant_id,
+            "Failed to delete participant"
+        );
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let Some(name) = name else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    revision::bump(&mut *tx, &event_id).await.map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to bump poll revision");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    audit::record(
+        &mut *tx,
+        Some(&event_id),
+        Some(&admin.admin_id),
+        "participant.removed",
+        json!({ "participant_id": participant_id, "name": name }),
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to record audit entry");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!(
+            error = ?e,
+            event_id = %event_id,
+            participant_id = %participant_id,
+            "Failed to commit participant removal"
+        );
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let time_slots = fetch_slot_counts(&pool, &event_id).await.map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch time slots");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(RemoveParticipantResponse {
+        participant_id,
+        time_slots,
+    }))
+}
@@ -0,0 +1,77 @@
+use axum::{extract::Path, extract::State, http::StatusCode, Json};
+use serde_json::json;
+use sqlx::AnyPool;
+
+use crate::audit;
+use crate::auth::{require_deleted_event_role, AdminContext};
+use crate::models::EventRole;
+use crate::request_id::ErrorResponse;
+use crate::revision;
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct RestorePollResponse {
+    pub id: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/poll/{id}/restore",
+    tag = "polls",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(("id" = String, Path, description = "Poll id")),
+    responses(
+        (status = 200, description = "Poll restored from the trash", body = RestorePollResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the owner role on the poll", body = ErrorResponse),
+        (status = 404, description = "No such poll in the trash", body = ErrorResponse),
+    )
+)]
+pub async fn restore_poll(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path(id): Path<String>,
+) -> Result<Json<RestorePollResponse>, StatusCode> {
+    require_deleted_event_role(&pool, &admin, &id, EventRole::Owner).await?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let title: Option<String> = sqlx::query_scalar(
+        r#"
+        UPDATE events
+        SET deleted_at = NULL
+        WHERE id = $1 AND deleted_at IS NOT NULL
+        RETURNING title
+        "#,
+    )
+    .bind(&id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let Some(title) = title else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    revision::bump(&mut *tx, &id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &mut *tx,
+        Some(&id),
+        Some(&admin.admin_id),
+        "event.restored",
+        json!({ "title": title }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(RestorePollResponse { id }))
+}
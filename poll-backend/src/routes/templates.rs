@@ -0,0 +1,334 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::AnyPool;
+use uuid::Uuid;
+
+use crate::auth::{require_event_role, AdminContext, CreatePolls, Scoped};
+use crate::metrics;
+use crate::models::{
+    parse_timestamp, CreateFromTemplateRequest, EventRole, EventRow, SaveTemplateRequest,
+    TemplateResponse, TimeSlotInput,
+};
+use crate::request_id::ErrorResponse;
+use crate::routes::create_poll::{insert_event, CreateEventResponse};
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct DeleteTemplateResponse {
+    pub id: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct SlotTimesRow {
+    starts_at: String,
+    ends_at: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct TemplateRow {
+    title: String,
+    description: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct TemplateSlotRow {
+    start_offset_minutes: i64,
+    duration_minutes: i64,
+}
+
+/// Saves an existing poll's title, description and slot layout as a named template.
+#[utoipa::path(
+    post,
+    path = "/api/v1/poll/{id}/template",
+    tag = "templates",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(("id" = String, Path, description = "Poll id")),
+    request_body = SaveTemplateRequest,
+    responses(
+        (status = 201, description = "Template saved", body = TemplateResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the viewer role on the poll", body = ErrorResponse),
+        (status = 404, description = "No such poll", body = ErrorResponse),
+        (status = 409, description = "A template with that name exists", body = ErrorResponse),
+        (status = 422, description = "Empty name, or a poll without slots or with a slot that doesn't end after it starts", body = ErrorResponse),
+    )
+)]
+pub async fn save_template(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path(event_id): Path<String>,
+    Json(payload): Json<SaveTemplateRequest>,
+) -> Result<(StatusCode, Json<TemplateResponse>), StatusCode> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    require_event_role(&pool, &admin, &event_id, EventRole::Viewer).await?;
+
+    let event = sqlx::query_as::<_, EventRow>(
+        "SELECT id, title, description, created_at, admin_id, organization_id FROM events WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(&event_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch event");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let slots = sqlx::query_as::<_, SlotTimesRow>(
+        "SELECT starts_at, ends_at FROM time_slots WHERE event_id = $1",
+    )
+    .bind(&event_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, event_id = %event_id, "Failed to fetch time slots");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .iter()
+    .map(|slot| {
+        Some(TimeSlotInput {
+            starts_at: parse_timestamp(&slot.starts_at)?,
+            ends_at: parse_timestamp(&slot.ends_at)?,
+        })
+    })
+    .collect::<Option<Vec<_>>>()
+    .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if !slots.iter().all(TimeSlotInput::ends_after_start) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let Some(first_start) = slots.iter().map(|slot| slot.starts_at).min() else {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    };
+
+    let template = TemplateResponse {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        title: event.title,
+        description: event.description,
+        slot_count: slots.len() as i64,
+        created_at: Utc::now().to_rfc3339(),
+    };
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let template_insert = sqlx::query(
+        r#"
+        INSERT INTO poll_templates (id, admin_id, name, title, description, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(&template.id)
+    .bind(&admin.admin_id)
+    .bind(&template.name)
+    .bind(&template.title)
+    .bind(&template.description)
+    .bind(&template.created_at)
+    .execute(&mut *tx)
+    .await;
+
+    if let Err(err) = template_insert {
+        if let sqlx::Error::Database(db_err) = &err {
+            if db_err.is_unique_violation() {
+                return Err(StatusCode::CONFLICT);
+            }
+        }
+        tracing::error!(error = ?err, event_id = %event_id, "Failed to insert template");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    for slot in &slots {
+        sqlx::query(
+            r#"
+            INSERT INTO poll_template_slots (id, template_id, start_offset_minutes, duration_minutes)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&template.id)
+        .bind((slot.starts_at - first_start).num_minutes())
+        .bind((slot.ends_at - slot.starts_at).num_minutes())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = ?e, template_id = %template.id, "Failed to insert template slot");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/templates",
+    tag = "templates",
+    security(("session_cookie" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "The admin's templates", body = Vec<TemplateResponse>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+pub async fn list_templates(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+) -> Result<Json<Vec<TemplateResponse>>, StatusCode> {
+    let templates = sqlx::query_as::<_, TemplateResponse>(
+        r#"
+        SELECT
+            t.id,
+            t.name,
+            t.title,
+            t.description,
+            (SELECT COUNT(*) FROM poll_template_slots s WHERE s.template_id = t.id) AS slot_count,
+            t.created_at
+        FROM poll_templates t
+        WHERE t.admin_id = $1
+        ORDER BY t.name ASC
+        "#,
+    )
+    .bind(&admin.admin_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(templates))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/templates/{id}",
+    tag = "templates",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(("id" = String, Path, description = "Template id")),
+    responses(
+        (status = 200, description = "Template deleted", body = DeleteTemplateResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No such template", body = ErrorResponse),
+    )
+)]
+pub async fn delete_template(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path(template_id): Path<String>,
+) -> Result<Json<DeleteTemplateResponse>, StatusCode> {
+    let result = sqlx::query("DELETE FROM poll_templates WHERE id = $1 AND admin_id = $2")
+        .bind(&template_id)
+        .bind(&admin.admin_id)
+        .execute(&pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(DeleteTemplateResponse { id: template_id }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/templates/{id}/poll",
+    tag = "templates",
+    security(("session_cookie" = []), ("bearer" = ["polls:create"])),
+    params(("id" = String, Path, description = "Template id")),
+    request_body = CreateFromTemplateRequest,
+    responses(
+        (status = 201, description = "Poll created", body = CreateEventResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No such template", body = ErrorResponse),
+        (status = 422, description = "Empty title, or slots out of range or without a positive length", body = ErrorResponse),
+    )
+)]
+pub async fn create_poll_from_template(
+    State(pool): State<AnyPool>,
+    Scoped { admin, .. }: Scoped<CreatePolls>,
+    Path(template_id): Path<String>,
+    Json(payload): Json<CreateFromTemplateRequest>,
+) -> Result<(StatusCode, Json<CreateEventResponse>), StatusCode> {
+    let template = sqlx::query_as::<_, TemplateRow>(
+        "SELECT title, description FROM poll_templates WHERE id = $1 AND admin_id = $2",
+    )
+    .bind(&template_id)
+    .bind(&admin.admin_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let title = match payload.title {
+        Some(title) if title.trim().is_empty() => return Err(StatusCode::UNPROCESSABLE_ENTITY),
+        Some(title) => title,
+        None => template.title,
+    };
+
+    let time_slots = sqlx::query_as::<_, TemplateSlotRow>(
+        r#"
+        SELECT start_offset_minutes, duration_minutes
+        FROM poll_template_slots
+        WHERE template_id = $1
+        ORDER BY start_offset_minutes ASC
+        "#,
+    )
+    .bind(&template_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .iter()
+    .map(|slot| {
+        let starts_at = payload
+            .first_slot_starts_at
+            .checked_add_signed(chrono::Duration::try_minutes(slot.start_offset_minutes)?)?;
+        let ends_at =
+            starts_at.checked_add_signed(chrono::Duration::try_minutes(slot.duration_minutes)?)?;
+        Some(TimeSlotInput { starts_at, ends_at }).filter(TimeSlotInput::ends_after_start)
+    })
+    .collect::<Option<Vec<_>>>()
+    .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let event = EventRow::new(title, template.description, admin.admin_id, None);
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    insert_event(
+        &mut tx,
+        &event,
+        &time_slots,
+        Some(json!({ "template_id": template_id })),
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, template_id = %template_id, "Failed to insert event from template");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    metrics::poll_created();
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateEventResponse { id: event.id }),
+    ))
+}
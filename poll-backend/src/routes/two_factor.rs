@@ -0,0 +1,689 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use rand_core::{OsRng, RngCore};
+use serde_json::json;
+use sqlx::{AnyConnection, AnyExecutor, AnyPool};
+use std::sync::Arc;
+
+use crate::audit;
+use crate::auth::{create_session, hash_token, session_cookies, AdminContext, SessionCookies};
+use crate::client_ip::ClientIp;
+use crate::clock::Clock;
+use crate::db::Backend;
+use crate::models::{
+    AdminAuthResponse, DisableTwoFactorRequest, OkResponse, RecoveryCodesResponse,
+    TotpCodeRequest, TotpSetupResponse, TwoFactorChallengeResponse, TwoFactorLoginRequest,
+    TwoFactorStatusResponse,
+};
+use crate::policy::AuthPolicy;
+use crate::request_id::ErrorResponse;
+use crate::routes::admin_account::confirm_identity;
+use crate::totp;
+
+/// Name authenticator apps show next to the account.
+const TOTP_ISSUER: &str = "Good Times";
+/// How long the second login step may take after the password was accepted.
+const CHALLENGE_TTL_MINUTES: i64 = 5;
+/// Wrong codes allowed per challenge before the password has to be entered again.
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+/// Wrong codes in a row, across challenges, before the second step locks.
+/// Each further miss once the lock is over doubles the next lock.
+const MAX_FAILED_ATTEMPTS: i32 = 10;
+const FIRST_LOCK_MINUTES: i64 = 15;
+const MAX_LOCK_MINUTES: i64 = 24 * 60;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(sqlx::FromRow)]
+struct TotpRow {
+    secret: String,
+    last_used_step: Option<i64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ChallengeRow {
+    admin_id: String,
+    name: String,
+    attempts: i32,
+}
+
+#[derive(sqlx::FromRow)]
+struct FailuresRow {
+    failed_attempts: i32,
+    locked_until: Option<String>,
+}
+
+/// Recovery codes are compared case-insensitively and without separators.
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 5];
+    OsRng.fill_bytes(&mut bytes);
+    let code = hex::encode(bytes);
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+/// How long the second step stays locked after `failed_attempts` wrong codes
+/// in a row, if at all.
+fn lock_duration(failed_attempts: i32) -> Option<chrono::Duration> {
+    let beyond = u32::try_from(failed_attempts.checked_sub(MAX_FAILED_ATTEMPTS)?).ok()?;
+    let minutes = FIRST_LOCK_MINUTES
+        .saturating_mul(1i64 << beyond.min(16))
+        .min(MAX_LOCK_MINUTES);
+    Some(chrono::Duration::minutes(minutes))
+}
+
+pub async fn two_factor_enabled<'e>(
+    executor: impl AnyExecutor<'e>,
+    admin_id: &str,
+) -> Result<bool, sqlx::Error> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM admin_totp WHERE admin_id = $1 AND enabled_at IS NOT NULL",
+    )
+    .bind(admin_id)
+    .fetch_one(executor)
+    .await?;
+    Ok(count > 0)
+}
+
+/// Issues the token for the second login step, after the password checked out.
+/// Earlier challenges of the admin stop working, so logging in again doesn't
+/// hand out more tries.
+pub async fn create_login_challenge(
+    pool: &AnyPool,
+    admin_id: &str,
+    now: DateTime<Utc>,
+) -> Result<TwoFactorChallengeResponse, sqlx::Error> {
+    let mut token_bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut token_bytes);
+    let token = hex::encode(token_bytes);
+    let expires_at = (now + chrono::Duration::minutes(CHALLENGE_TTL_MINUTES)).to_rfc3339();
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM admin_login_challenges WHERE admin_id = $1")
+        .bind(admin_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO admin_login_challenges (token_hash, admin_id, created_at, expires_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(hash_token(&token))
+    .bind(admin_id)
+    .bind(now.to_rfc3339())
+    .bind(&expires_at)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(TwoFactorChallengeResponse {
+        two_factor_required: true,
+        challenge_token: token,
+        expires_at,
+    })
+}
+
+/// Checks an authenticator code against the admin's enabled secret and marks
+/// its time step as used.
+async fn use_totp_code(
+    conn: &mut AnyConnection,
+    admin_id: &str,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let sql = format!(
+        r#"
+        SELECT secret, last_used_step
+        FROM admin_totp
+        WHERE admin_id = $1 AND enabled_at IS NOT NULL
+        {}
+        "#,
+        Backend::of(conn).for_update(),
+    );
+    let row = sqlx::query_as::<_, TotpRow>(&sql)
+        .bind(admin_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    let Some(row) = row else {
+        return Ok(false);
+    };
+    let Some(secret) = totp::decode_secret(&row.secret) else {
+        return Ok(false);
+    };
+    let Some(step) = totp::verify(&secret, code, now, row.last_used_step) else {
+        return Ok(false);
+    };
+
+    sqlx::query("UPDATE admin_totp SET last_used_step = $2 WHERE admin_id = $1")
+        .bind(admin_id)
+        .bind(step)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(true)
+}
+
+async fn use_recovery_code(
+    conn: &mut AnyConnection,
+    admin_id: &str,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE admin_recovery_codes
+        SET used_at = $3
+        WHERE admin_id = $1 AND code_hash = $2 AND used_at IS NULL
+        "#,
+    )
+    .bind(admin_id)
+    .bind(hash_token(&normalize_recovery_code(code)))
+    .bind(now.to_rfc3339())
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Checks whichever second factor was supplied and returns its name for the
+/// audit log, or `None` when it was wrong or missing.
+async fn use_second_factor(
+    conn: &mut AnyConnection,
+    admin_id: &str,
+    code: Option<&str>,
+    recovery_code: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Option<&'static str>, sqlx::Error> {
+    let code = code.map(str::trim).filter(|code| !code.is_empty());
+    let recovery_code = recovery_code.map(str::trim).filter(|code| !code.is_empty());
+
+    match (code, recovery_code) {
+        (Some(code), None) => Ok(use_totp_code(conn, admin_id, code, now)
+            .await?
+            .then_some("totp")),
+        (None, Some(code)) => Ok(use_recovery_code(conn, admin_id, code, now)
+            .await?
+            .then_some("recovery_code")),
+        _ => Ok(None),
+    }
+}
+
+/// Replaces the admin's recovery codes with a fresh set and returns them in the clear.
+async fn replace_recovery_codes(
+    conn: &mut AnyConnection,
+    admin_id: &str,
+    now: DateTime<Utc>,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query("DELETE FROM admin_recovery_codes WHERE admin_id = $1")
+        .bind(admin_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect();
+
+    for code in &codes {
+        sqlx::query(
+            "INSERT INTO admin_recovery_codes (code_hash, admin_id, created_at) VALUES ($1, $2, $3)",
+        )
+        .bind(hash_token(&normalize_recovery_code(code)))
+        .bind(admin_id)
+        .bind(now.to_rfc3339())
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(codes)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/2fa",
+    tag = "two-factor",
+    security(("session_cookie" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "Whether two-factor authentication is on", body = TwoFactorStatusResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+pub async fn get_two_factor_status(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+) -> Result<Json<TwoFactorStatusResponse>, StatusCode> {
+    let enabled = two_factor_enabled(&pool, &admin.admin_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let recovery_codes_left: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM admin_recovery_codes WHERE admin_id = $1 AND used_at IS NULL",
+    )
+    .bind(&admin.admin_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(TwoFactorStatusResponse {
+        enabled,
+        recovery_codes_left,
+    }))
+}
+
+/// Generates a new secret for enrollment. It only takes effect once a code
+/// from it is confirmed with `enable_totp`; calling this again starts over.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/2fa/setup",
+    tag = "two-factor",
+    security(("session_cookie" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "A new secret to enroll", body = TotpSetupResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 409, description = "Already enabled", body = ErrorResponse),
+    )
+)]
+pub async fn setup_totp(
+    State(pool): State<AnyPool>,
+    State(clock): State<Arc<dyn Clock>>,
+    admin: AdminContext,
+) -> Result<Json<TotpSetupResponse>, StatusCode> {
+    let name: String = sqlx::query_scalar("SELECT name FROM admins WHERE id = $1")
+        .bind(&admin.admin_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let secret = totp::generate_secret();
+
+    // An enabled secret is left alone; it has to be disabled first
+    let result = sqlx::query(
+        r#"
+        INSERT INTO admin_totp (admin_id, secret, created_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (admin_id) DO UPDATE
+        SET secret = excluded.secret, created_at = excluded.created_at, last_used_step = NULL
+        WHERE admin_totp.enabled_at IS NULL
+        "#,
+    )
+    .bind(&admin.admin_id)
+    .bind(totp::encode_secret(&secret))
+    .bind(clock.now().to_rfc3339())
+    .execute(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    Ok(Json(TotpSetupResponse {
+        secret: totp::encode_secret(&secret),
+        provisioning_uri: totp::provisioning_uri(&secret, TOTP_ISSUER, &name),
+    }))
+}
+
+/// Confirms enrollment with a code from the authenticator app and hands out
+/// the recovery codes.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/2fa/enable",
+    tag = "two-factor",
+    security(("session_cookie" = []), ("bearer" = [])),
+    request_body = TotpCodeRequest,
+    responses(
+        (status = 200, description = "Enabled; the recovery codes are shown only here", body = RecoveryCodesResponse),
+        (status = 401, description = "Not signed in, or a wrong code", body = ErrorResponse),
+        (status = 409, description = "No setup in progress", body = ErrorResponse),
+    )
+)]
+pub async fn enable_totp(
+    State(pool): State<AnyPool>,
+    State(clock): State<Arc<dyn Clock>>,
+    admin: AdminContext,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, StatusCode> {
+    let now = clock.now();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let sql = format!(
+        r#"
+        SELECT secret, last_used_step
+        FROM admin_totp
+        WHERE admin_id = $1 AND enabled_at IS NULL
+        {}
+        "#,
+        Backend::of(&tx).for_update(),
+    );
+    let pending = sqlx::query_as::<_, TotpRow>(&sql)
+        .bind(&admin.admin_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::CONFLICT)?;
+
+    let secret = totp::decode_secret(&pending.secret).ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    let step = totp::verify(&secret, &payload.code, now, pending.last_used_step)
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    sqlx::query("UPDATE admin_totp SET enabled_at = $2, last_used_step = $3 WHERE admin_id = $1")
+        .bind(&admin.admin_id)
+        .bind(now.to_rfc3339())
+        .bind(step)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let recovery_codes = replace_recovery_codes(&mut tx, &admin.admin_id, now)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(&mut *tx, None, Some(&admin.admin_id), "admin.2fa_enabled", json!({}))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Swaps the remaining recovery codes for a fresh set. Needs a current code.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/2fa/recovery-codes",
+    tag = "two-factor",
+    security(("session_cookie" = []), ("bearer" = [])),
+    request_body = TotpCodeRequest,
+    responses(
+        (status = 200, description = "The new recovery codes, shown only here", body = RecoveryCodesResponse),
+        (status = 401, description = "Not signed in, two-factor authentication is off, or a wrong code", body = ErrorResponse),
+    )
+)]
+pub async fn regenerate_recovery_codes(
+    State(pool): State<AnyPool>,
+    State(clock): State<Arc<dyn Clock>>,
+    admin: AdminContext,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, StatusCode> {
+    let now = clock.now();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if !use_totp_code(&mut tx, &admin.admin_id, &payload.code, now)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let recovery_codes = replace_recovery_codes(&mut tx, &admin.admin_id, now)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Turns two-factor authentication off. Needs the password, or a recent login
+/// for admins without one, and a second factor.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/2fa/disable",
+    tag = "two-factor",
+    security(("session_cookie" = []), ("bearer" = [])),
+    request_body = DisableTwoFactorRequest,
+    responses(
+        (status = 200, description = "Two-factor authentication is off", body = OkResponse),
+        (status = 401, description = "Not signed in, a wrong password or code, or an admin without a password signed in too long ago", body = ErrorResponse),
+    )
+)]
+pub async fn disable_totp(
+    State(pool): State<AnyPool>,
+    State(clock): State<Arc<dyn Clock>>,
+    admin: AdminContext,
+    Json(payload): Json<DisableTwoFactorRequest>,
+) -> Result<Json<OkResponse>, StatusCode> {
+    let name = confirm_identity(&pool, &admin, payload.password.as_deref()).await?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let method = use_second_factor(
+        &mut tx,
+        &admin.admin_id,
+        payload.code.as_deref(),
+        payload.recovery_code.as_deref(),
+        clock.now(),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    sqlx::query("DELETE FROM admin_totp WHERE admin_id = $1")
+        .bind(&admin.admin_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query("DELETE FROM admin_recovery_codes WHERE admin_id = $1")
+        .bind(&admin.admin_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &mut *tx,
+        None,
+        Some(&admin.admin_id),
+        "admin.2fa_disabled",
+        json!({ "name": name, "method": method }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(OkResponse { ok: true }))
+}
+
+/// Second login step: trades a challenge token and a code for a session.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/login/2fa",
+    tag = "auth",
+    request_body = TwoFactorLoginRequest,
+    responses(
+        (status = 200, description = "Signed in; sets the session cookies", body = AdminAuthResponse),
+        (status = 401, description = "Unknown or expired challenge, or a wrong code", body = ErrorResponse),
+        (status = 429, description = "Too many wrong codes; locked for a while", body = ErrorResponse),
+    )
+)]
+pub async fn complete_login(
+    State(pool): State<AnyPool>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(policy): State<Arc<AuthPolicy>>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<TwoFactorLoginRequest>,
+) -> Result<(SessionCookies, Json<AdminAuthResponse>), StatusCode> {
+    let now = clock.now();
+    let token_hash = hash_token(payload.challenge_token.trim());
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // The name comes from a subquery so only the challenge row gets locked
+    let backend = Backend::of(&tx);
+    let sql = format!(
+        r#"
+        SELECT c.admin_id, (SELECT a.name FROM admins a WHERE a.id = c.admin_id) AS name, c.attempts
+        FROM admin_login_challenges c
+        WHERE c.token_hash = $1 AND {expires_at} > {now}
+        {for_update}
+        "#,
+        expires_at = backend.timestamp("c.expires_at"),
+        now = backend.timestamp("$2"),
+        for_update = backend.for_update(),
+    );
+    let challenge = sqlx::query_as::<_, ChallengeRow>(&sql)
+        .bind(&token_hash)
+        .bind(now.to_rfc3339())
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Locking the row serializes attempts on the admin's other challenges
+    let sql = format!(
+        "SELECT failed_attempts, locked_until FROM admin_totp WHERE admin_id = $1 {}",
+        backend.for_update(),
+    );
+    let failures = sqlx::query_as::<_, FailuresRow>(&sql)
+        .bind(&challenge.admin_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let failed_attempts = failures.as_ref().map_or(0, |row| row.failed_attempts);
+    let locked = failures
+        .as_ref()
+        .and_then(|row| row.locked_until.as_deref())
+        .and_then(|until| DateTime::parse_from_rfc3339(until).ok())
+        .is_some_and(|until| until > now);
+    if locked {
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let method = use_second_factor(
+        &mut tx,
+        &challenge.admin_id,
+        payload.code.as_deref(),
+        payload.recovery_code.as_deref(),
+        now,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let Some(method) = method else {
+        // Count the miss, and give up on the challenge once it ran out of tries
+        let query = if challenge.attempts + 1 >= MAX_CHALLENGE_ATTEMPTS {
+            "DELETE FROM admin_login_challenges WHERE token_hash = $1"
+        } else {
+            "UPDATE admin_login_challenges SET attempts = attempts + 1 WHERE token_hash = $1"
+        };
+        sqlx::query(query)
+            .bind(&token_hash)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let failed_attempts = failed_attempts.saturating_add(1);
+        let locked_until = lock_duration(failed_attempts).map(|lock| (now + lock).to_rfc3339());
+        sqlx::query(
+            "UPDATE admin_totp SET failed_attempts = $2, locked_until = $3 WHERE admin_id = $1",
+        )
+        .bind(&challenge.admin_id)
+        .bind(failed_attempts)
+        .bind(locked_until)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        tx.commit()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    sqlx::query("DELETE FROM admin_login_challenges WHERE token_hash = $1")
+        .bind(&token_hash)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query(
+        "UPDATE admin_totp SET failed_attempts = 0, locked_until = NULL WHERE admin_id = $1",
+    )
+    .bind(&challenge.admin_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let session = create_session(&mut *tx, &challenge.admin_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &mut *tx,
+        None,
+        Some(&challenge.admin_id),
+        "admin.login",
+        json!({ "name": challenge.name, "second_factor": method, "ip": ip }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((
+        session_cookies(&session, policy.secure_cookies),
+        Json(AdminAuthResponse {
+            token: session.token,
+            admin_id: challenge.admin_id,
+            name: challenge.name,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locks_after_too_many_misses_and_doubles_the_lock() {
+        assert_eq!(lock_duration(MAX_FAILED_ATTEMPTS - 1), None);
+        assert_eq!(
+            lock_duration(MAX_FAILED_ATTEMPTS),
+            Some(chrono::Duration::minutes(15))
+        );
+        assert_eq!(
+            lock_duration(MAX_FAILED_ATTEMPTS + 1),
+            Some(chrono::Duration::minutes(30))
+        );
+        assert_eq!(
+            lock_duration(MAX_FAILED_ATTEMPTS + 20),
+            Some(chrono::Duration::minutes(MAX_LOCK_MINUTES))
+        );
+        assert_eq!(
+            lock_duration(i32::MAX),
+            Some(chrono::Duration::minutes(MAX_LOCK_MINUTES))
+        );
+    }
+}
@@ -1,147 +1,54 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
     Json,
 };
-use sqlx::PgPool;
+use std::sync::Arc;
 
-use crate::models::UpdateVotesRequest;
+use crate::metrics;
+use crate::models::{UnknownTimeSlotsResponse, UpdateVotesRequest};
+use crate::repository::EventRepository;
+use crate::request_id::ErrorResponse;
+use crate::votes::{self, VoteError};
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, utoipa::ToSchema)]
 pub struct UpdateVotesResponse {
     pub participant_id: String,
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/poll/{id}/participant/{participant_id}",
+    tag = "votes",
+    params(
+        ("id" = String, Path, description = "Poll id"),
+        ("participant_id" = String, Path, description = "Participant id"),
+    ),
+    request_body = UpdateVotesRequest,
+    responses(
+        (status = 200, description = "Answers replaced", body = UpdateVotesResponse),
+        (status = 404, description = "No such poll or participant", body = ErrorResponse),
+        (status = 422, description = "No answers, or slots that aren't part of the poll, which are listed", body = UnknownTimeSlotsResponse),
+    )
+)]
 pub async fn update_votes(
-    State(pool): State<PgPool>,
+    State(events): State<Arc<dyn EventRepository>>,
     Path((event_id, participant_id)): Path<(String, String)>,
     Json(payload): Json<UpdateVotesRequest>,
-) -> Result<Json<UpdateVotesResponse>, StatusCode> {
-    if payload.votes.is_empty() {
-        return Err(StatusCode::UNPROCESSABLE_ENTITY);
-    }
-
-    let participant_exists: i64 =
-        sqlx::query_scalar("SELECT COUNT(*) FROM participants WHERE id = $1 AND event_id = $2")
-            .bind(&participant_id)
-            .bind(&event_id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| {
-        tracing::error!(
-            error = ?e,
-            event_id = %event_id,
-            participant_id = %participant_id,
-            "Failed to check participant existence"
-        );
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    if participant_exists == 0 {
-        return Err(StatusCode::NOT_FOUND);
-    }
-
-    for vote in &payload.votes {
-        let slot_valid: i64 =
-            sqlx::query_scalar("SELECT COUNT(*) FROM time_slots WHERE id = $1 AND event_id = $2")
-                .bind(&vote.time_slot_id)
-                .bind(&event_id)
-        .fetch_one(&pool)
-        .await
-        .map_err(|e| {
-            tracing::error!(
-                error = ?e,
-                event_id = %event_id,
-                time_slot_id = %vote.time_slot_id,
-                "Failed to validate time slot"
-            );
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-        if slot_valid == 0 {
-            return Err(StatusCode::UNPROCESSABLE_ENTITY);
-        }
-    }
-
-    let mut tx = pool
-        .begin()
+) -> Result<Json<UpdateVotesResponse>, VoteError> {
+    votes::update(events.as_ref(), &event_id, &participant_id, &payload)
         .await
-        .map_err(|e| {
-            tracing::error!(
-                error = ?e,
-                event_id = %event_id,
-                participant_id = %participant_id,
-                "Failed to begin transaction"
-            );
-            StatusCode::INTERNAL_SERVER_ERROR
+        .inspect_err(|e| {
+            if let VoteError::Storage(e) = e {
+                tracing::error!(
+                    error = ?e,
+                    event_id = %event_id,
+                    participant_id = %participant_id,
+                    "Failed to update votes"
+                );
+            }
         })?;
 
-    sqlx::query(
-        r#"
-        DELETE FROM votes
-        WHERE participant_id = $1
-          AND time_slot_id IN (SELECT id FROM time_slots WHERE event_id = $2)
-        "#,
-    )
-    .bind(&participant_id)
-    .bind(&event_id)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!(
-            error = ?e,
-            event_id = %event_id,
-            participant_id = %participant_id,
-            "Failed to delete existing votes"
-        );
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let time_slot_ids: Vec<String> = payload
-        .votes
-        .iter()
-        .map(|vote| vote.time_slot_id.clone())
-        .collect();
-    let availabilities: Vec<i32> = payload
-        .votes
-        .iter()
-        .map(|vote| if vote.available { 1 } else { 0 })
-        .collect();
-
-    sqlx::query(
-        r#"
-        INSERT INTO votes (participant_id, time_slot_id, available)
-        SELECT $1, v.time_slot_id, v.available
-        FROM UNNEST($2::text[], $3::int[]) AS v(time_slot_id, available)
-        "#,
-    )
-    .bind(&participant_id)
-    .bind(&time_slot_ids)
-    .bind(&availabilities)
-    .execute(&mut *tx)
-    .await
-    .map_err(|e| {
-        tracing::error!(
-            error = ?e,
-            event_id = %event_id,
-            participant_id = %participant_id,
-            vote_count = time_slot_ids.len(),
-            "Failed to insert votes batch"
-        );
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    tx.commit()
-        .await
-        .map_err(|e| {
-            tracing::error!(
-                error = ?e,
-                event_id = %event_id,
-                participant_id = %participant_id,
-                "Failed to commit vote update transaction"
-            );
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    metrics::votes_updated();
 
     Ok(Json(UpdateVotesResponse { participant_id }))
 }
@@ -0,0 +1,363 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use rand_core::{OsRng, RngCore};
+use serde_json::json;
+use sqlx::AnyPool;
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::{require_event_role, AdminContext};
+use crate::db::Backend;
+use crate::models::{
+    CreateWebhookRequest, CreatedWebhookResponse, EventRole, QueuedDeliveryResponse,
+    WebhookDeliveryResponse, WebhookEventType, WebhookResponse,
+};
+use crate::request_id::ErrorResponse;
+use crate::webhooks::{self, AllowedNetworks};
+
+/// Deliveries listed per webhook, newest first.
+const DELIVERY_LOG_LIMIT: i64 = 100;
+
+#[derive(sqlx::FromRow)]
+struct WebhookRow {
+    id: String,
+    event_id: Option<String>,
+    url: String,
+    event_types: String,
+    created_at: String,
+}
+
+impl From<WebhookRow> for WebhookResponse {
+    fn from(row: WebhookRow) -> Self {
+        WebhookResponse {
+            id: row.id,
+            url: row.url,
+            events: WebhookEventType::parse_list(&row.event_types),
+            event_id: row.event_id,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct DeleteWebhookResponse {
+    pub id: String,
+}
+
+/// Checks that the webhook exists and belongs to `admin`.
+async fn require_own_webhook(
+    pool: &AnyPool,
+    admin: &AdminContext,
+    webhook_id: &str,
+) -> Result<(), StatusCode> {
+    let owned: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM webhooks WHERE id = $1 AND admin_id = $2")
+            .bind(webhook_id)
+            .bind(&admin.admin_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if owned == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    security(("session_cookie" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "The admin's webhooks", body = Vec<WebhookResponse>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    )
+)]
+pub async fn list_webhooks(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+) -> Result<Json<Vec<WebhookResponse>>, StatusCode> {
+    let webhooks = sqlx::query_as::<_, WebhookRow>(
+        r#"
+        SELECT id, event_id, url, event_types, created_at
+        FROM webhooks
+        WHERE admin_id = $1
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(&admin.admin_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        webhooks.into_iter().map(WebhookResponse::from).collect(),
+    ))
+}
+
+/// Registers a webhook for one event the admin owns, or for all of them when
+/// no event is given.
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    security(("session_cookie" = []), ("bearer" = [])),
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "The webhook, with its signing secret shown only here", body = CreatedWebhookResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Needs the owner role on the poll", body = ErrorResponse),
+        (status = 404, description = "No such poll", body = ErrorResponse),
+        (status = 422, description = "Not an http(s) URL, an internal address, or no events", body = ErrorResponse),
+    )
+)]
+pub async fn create_webhook(
+    State(pool): State<AnyPool>,
+    State(networks): State<Arc<AllowedNetworks>>,
+    admin: AdminContext,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<CreatedWebhookResponse>), StatusCode> {
+    let url = payload.url.trim();
+    if payload.events.is_empty() || !networks.permits_url(url).await {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    if let Some(event_id) = &payload.event_id {
+        require_event_role(&pool, &admin, event_id, EventRole::Owner).await?;
+    }
+
+    let mut events = payload.events;
+    events.sort_by_key(|event_type| event_type.as_str());
+    events.dedup();
+
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    let secret = format!("whsec_{}", hex::encode(secret));
+
+    let webhook = WebhookResponse {
+        id: Uuid::new_v4().to_string(),
+        url: url.to_string(),
+        events,
+        event_id: payload.event_id,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO webhooks (id, admin_id, event_id, url, secret, event_types, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(&webhook.id)
+    .bind(&admin.admin_id)
+    .bind(&webhook.event_id)
+    .bind(&webhook.url)
+    .bind(&secret)
+    .bind(WebhookEventType::join_list(&webhook.events))
+    .bind(&webhook.created_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = ?e, "Failed to insert webhook");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // The URL stays out of the audit log: chat services put credentials in it
+    audit::record(
+        &mut *tx,
+        webhook.event_id.as_deref(),
+        Some(&admin.admin_id),
+        "webhook.created",
+        json!({ "webhook_id": webhook.id, "events": webhook.events }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedWebhookResponse { webhook, secret }),
+    ))
+}
+
+/// Removes a webhook along with its pending deliveries and delivery log.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{id}",
+    tag = "webhooks",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "Webhook removed", body = DeleteWebhookResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No such webhook", body = ErrorResponse),
+    )
+)]
+pub async fn delete_webhook(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path(webhook_id): Path<String>,
+) -> Result<Json<DeleteWebhookResponse>, StatusCode> {
+    let event_id: Option<Option<String>> = sqlx::query_scalar(
+        "DELETE FROM webhooks WHERE id = $1 AND admin_id = $2 RETURNING event_id",
+    )
+    .bind(&webhook_id)
+    .bind(&admin.admin_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let Some(event_id) = event_id else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    audit::record(
+        &pool,
+        event_id.as_deref(),
+        Some(&admin.admin_id),
+        "webhook.deleted",
+        json!({ "webhook_id": webhook_id }),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(DeleteWebhookResponse { id: webhook_id }))
+}
+
+/// Recent deliveries of a webhook, newest first, with the outcome of the last attempt.
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "The latest deliveries, newest first", body = Vec<WebhookDeliveryResponse>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No such webhook", body = ErrorResponse),
+    )
+)]
+pub async fn list_deliveries(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path(webhook_id): Path<String>,
+) -> Result<Json<Vec<WebhookDeliveryResponse>>, StatusCode> {
+    require_own_webhook(&pool, &admin, &webhook_id).await?;
+
+    let sql = format!(
+        r#"
+        SELECT id, event_type, status, attempts,
+               CASE WHEN status = 'pending' THEN next_attempt_at END AS next_attempt_at,
+               last_attempt_at,
+               response_status, last_error, created_at
+        FROM webhook_deliveries
+        WHERE webhook_id = $1
+        ORDER BY {} DESC
+        LIMIT $2
+        "#,
+        Backend::of_pool(&pool).timestamp("created_at"),
+    );
+    let deliveries = sqlx::query_as::<_, WebhookDeliveryResponse>(&sql)
+        .bind(&webhook_id)
+        .bind(DELIVERY_LOG_LIMIT)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = ?e, webhook_id = %webhook_id, "Failed to list webhook deliveries");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(deliveries))
+}
+
+/// Queues a `ping` delivery so owners can check their receiver.
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/{id}/ping",
+    tag = "webhooks",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 202, description = "Ping queued", body = QueuedDeliveryResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No such webhook", body = ErrorResponse),
+    )
+)]
+pub async fn ping_webhook(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path(webhook_id): Path<String>,
+) -> Result<(StatusCode, Json<QueuedDeliveryResponse>), StatusCode> {
+    require_own_webhook(&pool, &admin, &webhook_id).await?;
+
+    let delivery_id = webhooks::enqueue_ping(&pool, &webhook_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(QueuedDeliveryResponse { delivery_id }),
+    ))
+}
+
+/// Puts a delivery back in the queue to be sent right away, with a fresh
+/// set of attempts.
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/{id}/deliveries/{delivery_id}/retry",
+    tag = "webhooks",
+    security(("session_cookie" = []), ("bearer" = [])),
+    params(
+        ("id" = String, Path, description = "Webhook id"),
+        ("delivery_id" = String, Path, description = "Delivery id"),
+    ),
+    responses(
+        (status = 202, description = "Delivery queued again", body = QueuedDeliveryResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "No such webhook or delivery", body = ErrorResponse),
+    )
+)]
+pub async fn retry_delivery(
+    State(pool): State<AnyPool>,
+    admin: AdminContext,
+    Path((webhook_id, delivery_id)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<QueuedDeliveryResponse>), StatusCode> {
+    require_own_webhook(&pool, &admin, &webhook_id).await?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = 'pending', attempts = 0, next_attempt_at = $3
+        WHERE id = $1 AND webhook_id = $2
+        "#,
+    )
+    .bind(&delivery_id)
+    .bind(&webhook_id)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(QueuedDeliveryResponse { delivery_id }),
+    ))
+}
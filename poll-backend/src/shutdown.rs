@@ -0,0 +1,72 @@
+//! Stopping in order on SIGTERM or Ctrl-C: the server stops accepting
+//! connections and drains, background tasks finish their current round, and
+//! only then does the pool close.
+
+use tokio::sync::watch;
+
+/// Tells everyone holding a [`ShutdownSignal`] to stop.
+pub struct Shutdown {
+    sender: watch::Sender<bool>,
+}
+
+/// Resolves once shutdown has started.
+#[derive(Clone)]
+pub struct ShutdownSignal(watch::Receiver<bool>);
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> ShutdownSignal {
+        ShutdownSignal(self.sender.subscribe())
+    }
+
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownSignal {
+    pub async fn wait(&mut self) {
+        // A dropped sender means nobody is left to say otherwise, so stop too
+        let _ = self.0.wait_for(|stopping| *stopping).await;
+    }
+}
+
+/// Waits for SIGTERM, as sent by orchestrators, or Ctrl-C.
+pub async fn terminate_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = ?e, "Failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = ?e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
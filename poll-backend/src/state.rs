@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use metrics_exporter_prometheus::PrometheusHandle;
+use sqlx::AnyPool;
+
+use crate::client_ip::TrustedProxies;
+use crate::clock::Clock;
+use crate::oidc::OidcClient;
+use crate::password_reset::ResetLinkSender;
+use crate::policy::AuthPolicy;
+use crate::repository::EventRepository;
+use crate::revision::PollCache;
+use crate::webhooks::AllowedNetworks;
+
+/// Shared state handed to every route. Handlers that only talk to the
+/// database can keep extracting `State<AnyPool>`.
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: AnyPool,
+    pub events: Arc<dyn EventRepository>,
+    pub reset_sender: Arc<dyn ResetLinkSender>,
+    pub auth_policy: Arc<AuthPolicy>,
+    /// Base URL of the frontend, used to build links sent to admins
+    pub public_base_url: String,
+    pub clock: Arc<dyn Clock>,
+    /// Set when an OpenID Connect provider is configured
+    pub oidc: Option<Arc<OidcClient>>,
+    pub trusted_proxies: Arc<TrustedProxies>,
+    pub poll_cache: Arc<PollCache>,
+    /// Internal networks webhooks may be registered for
+    pub webhook_networks: Arc<AllowedNetworks>,
+    /// Renders the `/metrics` page
+    pub metrics: PrometheusHandle,
+}
+
+impl FromRef<AppState> for AnyPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn EventRepository> {
+    fn from_ref(state: &AppState) -> Self {
+        state.events.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<AuthPolicy> {
+    fn from_ref(state: &AppState) -> Self {
+        state.auth_policy.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn Clock> {
+    fn from_ref(state: &AppState) -> Self {
+        state.clock.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<TrustedProxies> {
+    fn from_ref(state: &AppState) -> Self {
+        state.trusted_proxies.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<PollCache> {
+    fn from_ref(state: &AppState) -> Self {
+        state.poll_cache.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<AllowedNetworks> {
+    fn from_ref(state: &AppState) -> Self {
+        state.webhook_networks.clone()
+    }
+}
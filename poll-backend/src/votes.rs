@@ -0,0 +1,278 @@
+//! Rules for participants answering a poll, independent of HTTP and of the
+//! database behind [`EventRepository`].
+
+use std::collections::HashSet;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::models::{
+    ParticipantRow, SubmitVoteRequest, UnknownTimeSlotsResponse, UpdateVotesRequest, VoteInput,
+    VoteResponse,
+};
+use crate::repository::EventRepository;
+
+#[derive(Debug)]
+pub enum VoteError {
+    /// The participant name is blank
+    EmptyName,
+    /// An update without any answers
+    NoVotes,
+    /// The poll doesn't exist or is in the trash
+    EventNotFound,
+    /// Not a participant of the poll
+    ParticipantNotFound,
+    /// Answers for slots the poll doesn't have, with their ids
+    UnknownSlots(Vec<String>),
+    Storage(sqlx::Error),
+}
+
+impl VoteError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::EmptyName | Self::NoVotes | Self::UnknownSlots(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Self::EventNotFound | Self::ParticipantNotFound => StatusCode::NOT_FOUND,
+            Self::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Unknown slots are listed in the body; the rest get the usual error body.
+impl IntoResponse for VoteError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        match self {
+            Self::UnknownSlots(time_slot_ids) => (
+                status,
+                Json(UnknownTimeSlotsResponse {
+                    error: status.canonical_reason().unwrap_or_default().to_string(),
+                    time_slot_ids,
+                }),
+            )
+                .into_response(),
+            _ => status.into_response(),
+        }
+    }
+}
+
+impl From<sqlx::Error> for VoteError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Storage(e)
+    }
+}
+
+/// Adds a participant with their answers to the poll.
+pub async fn submit(
+    events: &dyn EventRepository,
+    event_id: &str,
+    request: &SubmitVoteRequest,
+) -> Result<ParticipantRow, VoteError> {
+    let name = request.participant_name.trim();
+    if name.is_empty() {
+        return Err(VoteError::EmptyName);
+    }
+    if !events.event_exists(event_id).await? {
+        return Err(VoteError::EventNotFound);
+    }
+    check_slots(events, event_id, &request.votes).await?;
+
+    let participant = ParticipantRow::new(event_id, name.to_string());
+    events.add_participant(&participant, &request.votes).await?;
+    Ok(participant)
+}
+
+/// Replaces a participant's answers, returning the previous ones.
+pub async fn update(
+    events: &dyn EventRepository,
+    event_id: &str,
+    participant_id: &str,
+    request: &UpdateVotesRequest,
+) -> Result<Vec<VoteResponse>, VoteError> {
+    if request.votes.is_empty() {
+        return Err(VoteError::NoVotes);
+    }
+    if !events.participant_exists(event_id, participant_id).await? {
+        return Err(VoteError::ParticipantNotFound);
+    }
+    check_slots(events, event_id, &request.votes).await?;
+
+    Ok(events
+        .replace_votes(event_id, participant_id, &request.votes)
+        .await?)
+}
+
+/// Every answer must be for one of the poll's own slots. Checks them all at
+/// once, so the error can name every offending slot.
+async fn check_slots(
+    events: &dyn EventRepository,
+    event_id: &str,
+    votes: &[VoteInput],
+) -> Result<(), VoteError> {
+    let mut seen = HashSet::new();
+    let time_slot_ids: Vec<&str> = votes
+        .iter()
+        .map(|vote| vote.time_slot_id.as_str())
+        .filter(|id| seen.insert(*id))
+        .collect();
+
+    let unknown = events.unknown_time_slots(event_id, &time_slot_ids).await?;
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(VoteError::UnknownSlots(unknown))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::InMemoryEventRepository;
+
+    fn votes(answers: &[(&str, bool)]) -> Vec<VoteInput> {
+        answers
+            .iter()
+            .map(|&(time_slot_id, available)| VoteInput {
+                time_slot_id: time_slot_id.to_string(),
+                available,
+            })
+            .collect()
+    }
+
+    fn submission(name: &str, answers: &[(&str, bool)]) -> SubmitVoteRequest {
+        SubmitVoteRequest {
+            participant_name: name.to_string(),
+            votes: votes(answers),
+        }
+    }
+
+    fn repository() -> InMemoryEventRepository {
+        let events = InMemoryEventRepository::default();
+        events.add_event("lunch", &["mon", "tue"]);
+        events.add_event("dinner", &["fri"]);
+        events
+    }
+
+    #[tokio::test]
+    async fn saves_participants_with_their_answers() {
+        let events = repository();
+        let request = submission("  Bob ", &[("tue", true), ("mon", false)]);
+
+        let participant = submit(&events, "lunch", &request).await.unwrap();
+        assert_eq!(participant.name, "Bob");
+        assert_eq!(participant.event_id, "lunch");
+        assert_eq!(events.participants("lunch").len(), 1);
+        assert_eq!(
+            events.votes(&participant.id),
+            [("mon".to_string(), false), ("tue".to_string(), true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn participants_may_skip_every_slot() {
+        let events = repository();
+        let participant = submit(&events, "lunch", &submission("Bob", &[]))
+            .await
+            .unwrap();
+        assert!(events.votes(&participant.id).is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejects_blank_names() {
+        let events = repository();
+        let result = submit(&events, "lunch", &submission("  ", &[("mon", true)])).await;
+        assert!(matches!(result, Err(VoteError::EmptyName)));
+    }
+
+    #[tokio::test]
+    async fn rejects_polls_that_are_missing_or_deleted() {
+        let events = repository();
+        let request = submission("Bob", &[("mon", true)]);
+        let result = submit(&events, "brunch", &request).await;
+        assert!(matches!(result, Err(VoteError::EventNotFound)));
+
+        events.delete_event("lunch");
+        let result = submit(&events, "lunch", &request).await;
+        assert!(matches!(result, Err(VoteError::EventNotFound)));
+    }
+
+    #[tokio::test]
+    async fn rejects_slots_of_other_polls_and_saves_nothing() {
+        let events = repository();
+        let request = submission("Bob", &[("mon", true), ("fri", true)]);
+
+        let result = submit(&events, "lunch", &request).await;
+        let error = result.unwrap_err();
+        assert!(matches!(&error, VoteError::UnknownSlots(ids) if ids == &["fri"]));
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(events.participants("lunch").is_empty());
+    }
+
+    #[tokio::test]
+    async fn names_every_unknown_slot_once() {
+        let events = repository();
+        let request = submission(
+            "Bob",
+            &[("sat", true), ("mon", true), ("fri", false), ("sat", false)],
+        );
+
+        let result = submit(&events, "lunch", &request).await;
+        let Err(VoteError::UnknownSlots(ids)) = result else {
+            panic!("expected unknown slots, got {result:?}");
+        };
+        assert_eq!(ids, ["sat", "fri"]);
+    }
+
+    #[tokio::test]
+    async fn updates_replace_every_answer() {
+        let events = repository();
+        let bob = submit(
+            &events,
+            "lunch",
+            &submission("Bob", &[("mon", true), ("tue", true)]),
+        )
+        .await
+        .unwrap();
+
+        let request = UpdateVotesRequest {
+            votes: votes(&[("tue", false)]),
+        };
+        let previous = update(&events, "lunch", &bob.id, &request).await.unwrap();
+        assert_eq!(previous.len(), 2);
+        assert!(previous.iter().all(|vote| vote.available));
+        assert_eq!(events.votes(&bob.id), [("tue".to_string(), false)]);
+    }
+
+    #[tokio::test]
+    async fn updates_need_answers_from_a_participant_of_the_poll() {
+        let events = repository();
+        let bob = submit(&events, "lunch", &submission("Bob", &[("mon", true)]))
+            .await
+            .unwrap();
+
+        let empty = UpdateVotesRequest { votes: Vec::new() };
+        let result = update(&events, "lunch", &bob.id, &empty).await;
+        assert!(matches!(result, Err(VoteError::NoVotes)));
+
+        let request = UpdateVotesRequest {
+            votes: votes(&[("fri", true)]),
+        };
+        let result = update(&events, "dinner", &bob.id, &request).await;
+        assert!(matches!(result, Err(VoteError::ParticipantNotFound)));
+
+        let result = update(&events, "lunch", &bob.id, &request).await;
+        assert!(matches!(result, Err(VoteError::UnknownSlots(_))));
+        assert_eq!(events.votes(&bob.id), [("mon".to_string(), true)]);
+
+        events.delete_event("lunch");
+        let request = UpdateVotesRequest {
+            votes: votes(&[("mon", false)]),
+        };
+        let result = update(&events, "lunch", &bob.id, &request).await;
+        assert!(matches!(result, Err(VoteError::ParticipantNotFound)));
+    }
+}
@@ -0,0 +1,595 @@
+//! Outgoing webhooks. Handlers queue a delivery per subscribed webhook inside
+//! their own transaction; a background task sends them, signed with the
+//! webhook's secret, and retries failures with exponential backoff.
+//!
+//! Receivers must be on the public internet unless their network is listed
+//! in [`AllowedNetworks`]; otherwise any admin could make the server call
+//! its own metadata endpoint or the private network.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use ipnet::IpNet;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{AnyConnection, AnyExecutor, AnyPool};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::db::Backend;
+use crate::metrics;
+use crate::models::{DeliveryStatus, EventRole, WebhookEventType};
+use crate::shutdown::ShutdownSignal;
+
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+pub const EVENT_HEADER: &str = "x-webhook-event";
+pub const DELIVERY_HEADER: &str = "x-webhook-delivery";
+
+/// How often the delivery task looks for due deliveries.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Deliveries sent per round.
+const BATCH_SIZE: i64 = 20;
+/// Covers resolving, connecting and the response.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// A claimed delivery becomes due again after this long, in case the process
+/// dies before recording the outcome. Outlasts a whole batch of requests that
+/// all time out, so no delivery is sent twice by two instances.
+const CLAIM_TIMEOUT: Duration =
+    Duration::from_secs(BATCH_SIZE as u64 * REQUEST_TIMEOUT.as_secs() + 60);
+/// Attempts before a delivery is marked failed; about two hours of retries.
+const MAX_ATTEMPTS: i32 = 8;
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+#[derive(sqlx::FromRow)]
+struct DueDelivery {
+    id: String,
+    event_type: String,
+    payload: String,
+    attempts: i32,
+    created_at: String,
+    url: String,
+    secret: String,
+}
+
+/// Only the status is kept of an answer, and only a class of a failure:
+/// neither may carry content from the receiver back to the admin.
+enum Outcome {
+    Delivered { status: u16 },
+    Rejected { status: u16 },
+    Unreachable { error: &'static str },
+}
+
+/// Internal networks webhooks may be sent to anyway, such as a receiver on
+/// the same host during development. Public addresses are always allowed.
+#[derive(Debug, Clone, Default)]
+pub struct AllowedNetworks(pub Vec<IpNet>);
+
+impl AllowedNetworks {
+    pub fn permits(&self, addr: IpAddr) -> bool {
+        !is_internal(addr) || self.0.iter().any(|net| net.contains(&addr))
+    }
+
+    /// Whether `url` may receive webhooks: http(s), naming a permitted
+    /// address or a host that resolves only to permitted ones. Hosts that
+    /// don't resolve yet pass; every delivery checks again.
+    pub async fn permits_url(&self, url: &str) -> bool {
+        let Ok(url) = url::Url::parse(url) else {
+            return false;
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        let port = url.port_or_known_default().unwrap_or(80);
+        match url.host() {
+            Some(url::Host::Ipv4(addr)) => self.permits(addr.into()),
+            Some(url::Host::Ipv6(addr)) => self.permits(addr.into()),
+            Some(url::Host::Domain(host)) => match tokio::net::lookup_host((host, port)).await {
+                Ok(mut addrs) => addrs.all(|addr| self.permits(addr.ip())),
+                Err(_) => true,
+            },
+            None => false,
+        }
+    }
+}
+
+/// Loopback, private, link-local, shared, multicast, reserved and
+/// unspecified addresses, and the IPv6 prefixes that translate to IPv4, which
+/// only make sense from inside the server's network.
+fn is_internal(addr: IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_multicast()
+                || a == 0
+                // 100.64.0.0/10, carrier-grade NAT
+                || (a == 100 && (64..128).contains(&b))
+                // 198.18.0.0/15, benchmarking
+                || (a == 198 && (b == 18 || b == 19))
+                // 240.0.0.0/4, reserved, broadcast included
+                || a >= 240
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_internal(v4.into()),
+            None => {
+                let segments = v6.segments();
+                v6.is_loopback()
+                    || v6.is_unspecified()
+                    || v6.is_unique_local()
+                    || v6.is_unicast_link_local()
+                    || v6.is_multicast()
+                    // fec0::/10, deprecated site-local
+                    || (segments[0] & 0xffc0) == 0xfec0
+                    // 64:ff9b::/96, NAT64
+                    || segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0]
+                    // 2002::/16, 6to4
+                    || segments[0] == 0x2002
+            }
+        },
+    }
+}
+
+/// Resolves receiver host names, leaving out addresses webhooks may not be
+/// sent to. Checking the addresses actually connected to keeps a host from
+/// passing registration and later resolving somewhere internal.
+struct PermittedResolver(Arc<AllowedNetworks>);
+
+/// No address of the receiver is permitted.
+#[derive(Debug)]
+struct BlockedAddress;
+
+impl std::fmt::Display for BlockedAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("receiver resolves only to internal addresses")
+    }
+}
+
+impl std::error::Error for BlockedAddress {}
+
+impl Resolve for PermittedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let networks = self.0.clone();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| networks.permits(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(BlockedAddress.into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// The class of a failed request, for the delivery log.
+fn error_class(error: &reqwest::Error) -> &'static str {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(e) = source {
+        if e.is::<BlockedAddress>() {
+            return "blocked_address";
+        }
+        source = e.source();
+    }
+    if error.is_timeout() {
+        "timeout"
+    } else if error.is_connect() {
+        "connection_failed"
+    } else {
+        "request_failed"
+    }
+}
+
+/// Queues `data` for every webhook of an owner of `event_id` that subscribed
+/// to `event_type`, either for this event or for all of theirs.
+pub async fn enqueue(
+    conn: &mut AnyConnection,
+    event_id: &str,
+    event_type: WebhookEventType,
+    data: Value,
+) -> Result<(), sqlx::Error> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let payload = json!({
+        "type": event_type.as_str(),
+        "event_id": event_id,
+        "created_at": now,
+        "data": data,
+    });
+
+    let webhooks: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT w.id, w.event_types
+        FROM webhooks w
+        WHERE (w.event_id IS NULL OR w.event_id = $1)
+          AND EXISTS (
+              SELECT 1 FROM event_access a
+              WHERE a.event_id = $1 AND a.admin_id = w.admin_id AND a.role = $2
+          )
+        "#,
+    )
+    .bind(event_id)
+    .bind(EventRole::Owner)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let subscribed = webhooks
+        .into_iter()
+        .filter(|(_, event_types)| WebhookEventType::parse_list(event_types).contains(&event_type));
+    for (webhook_id, _) in subscribed {
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries
+                (id, webhook_id, event_type, payload, status, attempts, next_attempt_at, created_at)
+            VALUES ($1, $2, $3, $4, 'pending', 0, $5, $5)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&webhook_id)
+        .bind(event_type.as_str())
+        .bind(payload.to_string())
+        .bind(&now)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Queues a `ping` for one webhook, to check the receiver is set up right.
+pub async fn enqueue_ping<'e>(
+    executor: impl AnyExecutor<'e>,
+    webhook_id: &str,
+) -> Result<String, sqlx::Error> {
+    let delivery_id = Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    let payload = json!({
+        "type": "ping",
+        "event_id": null,
+        "created_at": now,
+        "data": { "webhook_id": webhook_id },
+    });
+
+    sqlx::query(
+        r#"
+        INSERT INTO webhook_deliveries
+            (id, webhook_id, event_type, payload, status, attempts, next_attempt_at, created_at)
+        VALUES ($1, $2, 'ping', $3, 'pending', 0, $4, $4)
+        "#,
+    )
+    .bind(&delivery_id)
+    .bind(webhook_id)
+    .bind(payload.to_string())
+    .bind(&now)
+    .execute(executor)
+    .await?;
+
+    Ok(delivery_id)
+}
+
+/// `sha256=<hex>` of the HMAC over `"{timestamp}.{body}"`. Covering the
+/// timestamp lets receivers reject replays of old deliveries.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Wait before the attempt following `attempts` failed ones: 30s, 1m, 2m, …
+/// capped at an hour.
+fn retry_delay(attempts: i32) -> Duration {
+    let doublings = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    FIRST_RETRY_DELAY
+        .saturating_mul(2u32.pow(doublings))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Sends every delivery that is due and records how it went.
+pub async fn deliver_due(
+    pool: &AnyPool,
+    http: &reqwest::Client,
+    networks: &AllowedNetworks,
+) -> Result<usize, sqlx::Error> {
+    let now = chrono::Utc::now();
+    let reclaim_at = now + chrono::Duration::from_std(CLAIM_TIMEOUT).unwrap_or_default();
+
+    // Pushing next_attempt_at forward claims the rows, so another instance
+    // polling at the same time skips them
+    let backend = Backend::of_pool(pool);
+    let sql = format!(
+        r#"
+        UPDATE webhook_deliveries
+        SET next_attempt_at = $2
+        WHERE id IN (
+            SELECT id FROM webhook_deliveries
+            WHERE status = 'pending' AND {next_attempt_at} <= {now}
+            ORDER BY {next_attempt_at} ASC
+            LIMIT $3
+            {skip_locked}
+        )
+        RETURNING id, event_type, payload, attempts, created_at,
+            (SELECT url FROM webhooks w WHERE w.id = webhook_id) AS url,
+            (SELECT secret FROM webhooks w WHERE w.id = webhook_id) AS secret
+        "#,
+        next_attempt_at = backend.timestamp("next_attempt_at"),
+        now = backend.timestamp("$1"),
+        skip_locked = backend.skip_locked(),
+    );
+    let mut due = sqlx::query_as::<_, DueDelivery>(&sql)
+        .bind(now.to_rfc3339())
+        .bind(reclaim_at.to_rfc3339())
+        .bind(BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+
+    // RETURNING has no order; send the oldest first
+    due.sort_by_cached_key(|delivery| {
+        chrono::DateTime::parse_from_rfc3339(&delivery.created_at).ok()
+    });
+
+    for delivery in &due {
+        let outcome = send(http, networks, delivery).await;
+        record_outcome(pool, delivery, outcome).await?;
+    }
+
+    Ok(due.len())
+}
+
+async fn send(
+    http: &reqwest::Client,
+    networks: &AllowedNetworks,
+    delivery: &DueDelivery,
+) -> Outcome {
+    // Addresses in the URL itself never reach the resolver
+    let literal = url::Url::parse(&delivery.url)
+        .ok()
+        .and_then(|url| match url.host()? {
+            url::Host::Ipv4(addr) => Some(IpAddr::from(addr)),
+            url::Host::Ipv6(addr) => Some(IpAddr::from(addr)),
+            url::Host::Domain(_) => None,
+        });
+    if literal.is_some_and(|addr| !networks.permits(addr)) {
+        return Outcome::Unreachable {
+            error: "blocked_address",
+        };
+    }
+
+    let timestamp = chrono::Utc::now().timestamp();
+
+    let response = http
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(DELIVERY_HEADER, &delivery.id)
+        .header(EVENT_HEADER, &delivery.event_type)
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(
+            SIGNATURE_HEADER,
+            sign(&delivery.secret, timestamp, &delivery.payload),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => Outcome::Delivered {
+            status: response.status().as_u16(),
+        },
+        Ok(response) => Outcome::Rejected {
+            status: response.status().as_u16(),
+        },
+        Err(e) => {
+            tracing::debug!(delivery_id = %delivery.id, error = %e, "Webhook request failed");
+            Outcome::Unreachable {
+                error: error_class(&e),
+            }
+        }
+    }
+}
+
+async fn record_outcome(
+    pool: &AnyPool,
+    delivery: &DueDelivery,
+    outcome: Outcome,
+) -> Result<(), sqlx::Error> {
+    let now = chrono::Utc::now();
+    let attempts = delivery.attempts + 1;
+
+    let (status, response_status, error) = match outcome {
+        Outcome::Delivered { status } => (DeliveryStatus::Delivered, Some(status), None),
+        Outcome::Rejected { status } => (DeliveryStatus::Pending, Some(status), None),
+        Outcome::Unreachable { error } => (DeliveryStatus::Pending, None, Some(error)),
+    };
+    let status = if status == DeliveryStatus::Pending && attempts >= MAX_ATTEMPTS {
+        DeliveryStatus::Failed
+    } else {
+        status
+    };
+
+    metrics::webhook_delivery(match status {
+        DeliveryStatus::Delivered => "delivered",
+        DeliveryStatus::Pending => "retrying",
+        DeliveryStatus::Failed => "failed",
+    });
+
+    if status != DeliveryStatus::Delivered {
+        tracing::warn!(
+            delivery_id = %delivery.id,
+            attempts,
+            response_status,
+            error = error.unwrap_or_default(),
+            "Webhook delivery failed"
+        );
+    }
+
+    let next_attempt_at =
+        now + chrono::Duration::from_std(retry_delay(attempts)).unwrap_or_default();
+
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = $2, attempts = $3, next_attempt_at = $4, last_attempt_at = $5,
+            response_status = $6, last_error = $7
+        WHERE id = $1
+        "#,
+    )
+    .bind(&delivery.id)
+    .bind(status)
+    .bind(attempts)
+    .bind(next_attempt_at.to_rfc3339())
+    .bind(now.to_rfc3339())
+    .bind(response_status.map(i32::from))
+    .bind(error)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Sends due deliveries every [`POLL_INTERVAL`]. On shutdown the current
+/// round finishes; anything still queued waits for the next start.
+pub fn spawn(
+    pool: AnyPool,
+    networks: Arc<AllowedNetworks>,
+    mut shutdown: ShutdownSignal,
+) -> JoinHandle<()> {
+    let http = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .dns_resolver(Arc::new(PermittedResolver(networks.clone())))
+        // A redirect could point the signed payload anywhere
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .expect("HTTP client settings are valid");
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.wait() => break,
+            }
+            if let Err(e) = deliver_due(&pool, &http, &networks).await {
+                tracing::error!(error = ?e, "Failed to deliver webhooks");
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        // echo -n '1700000000.{"type":"ping"}' | openssl dgst -sha256 -hmac whsec_test
+        assert_eq!(
+            sign("whsec_test", 1700000000, r#"{"type":"ping"}"#),
+            "sha256=bc08c591847b765241711bcbe7067e3869a219e424d3fdd9d00b3b6f915baf97"
+        );
+    }
+
+    #[test]
+    fn permits_only_public_addresses_unless_allowed() {
+        let public = AllowedNetworks::default();
+        for addr in ["93.184.216.34", "198.20.0.1", "2606:2800:220:1::1"] {
+            assert!(public.permits(addr.parse().unwrap()), "{addr}");
+        }
+        for addr in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "224.0.0.1",
+            "239.255.255.250",
+            "198.18.0.1",
+            "198.19.255.255",
+            "240.0.0.1",
+            "255.255.255.255",
+            "ff02::1",
+            "64:ff9b::a01:203",
+            "2002:a01:203::1",
+            "fec0::1",
+        ] {
+            assert!(!public.permits(addr.parse().unwrap()), "{addr}");
+        }
+
+        let local = AllowedNetworks(vec!["127.0.0.0/8".parse().unwrap()]);
+        assert!(local.permits("127.0.0.1".parse().unwrap()));
+        assert!(!local.permits("10.1.2.3".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn rejects_urls_of_internal_hosts() {
+        let public = AllowedNetworks::default();
+        assert!(
+            !public
+                .permits_url("http://169.254.169.254/latest/meta-data")
+                .await
+        );
+        assert!(!public.permits_url("http://[::1]:8088/").await);
+        assert!(!public.permits_url("http://localhost:8088/").await);
+        assert!(!public.permits_url("ftp://93.184.216.34/").await);
+        assert!(public.permits_url("https://93.184.216.34/hook").await);
+
+        let local = AllowedNetworks(vec!["127.0.0.1/32".parse().unwrap()]);
+        assert!(local.permits_url("http://127.0.0.1:8088/").await);
+    }
+
+    #[tokio::test]
+    async fn checks_resolved_addresses_when_sending() {
+        let networks = Arc::new(AllowedNetworks::default());
+        let http = reqwest::Client::builder()
+            .dns_resolver(Arc::new(PermittedResolver(networks.clone())))
+            .build()
+            .unwrap();
+        let delivery = |url: &str| DueDelivery {
+            id: "d".to_string(),
+            event_type: "ping".to_string(),
+            payload: "{}".to_string(),
+            attempts: 0,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            url: url.to_string(),
+            secret: "whsec_test".to_string(),
+        };
+
+        for url in ["http://localhost:9/", "http://127.0.0.1:9/"] {
+            let outcome = send(&http, &networks, &delivery(url)).await;
+            assert!(
+                matches!(
+                    outcome,
+                    Outcome::Unreachable {
+                        error: "blocked_address"
+                    }
+                ),
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_an_hour() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(2), Duration::from_secs(60));
+        assert_eq!(retry_delay(3), Duration::from_secs(120));
+        assert_eq!(retry_delay(7), Duration::from_secs(32 * 60));
+        assert_eq!(retry_delay(8), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(100), MAX_RETRY_DELAY);
+    }
+}
@@ -0,0 +1,663 @@
+//! End-to-end flows through the HTTP API, each against its own database on
+//! every backend.
+
+mod common;
+
+use std::sync::Arc;
+
+use reqwest::Method;
+use serde_json::{json, Value};
+
+use common::{expect_json, run, run_with, TestApp};
+use poll_backend::policy::{AuthPolicy, SignupMode};
+
+fn slot_ids(poll: &Value) -> Vec<String> {
+    poll["time_slots"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|slot| slot["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn available_counts(poll: &Value) -> Vec<i64> {
+    poll["time_slots"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|slot| slot["available_count"].as_i64().unwrap())
+        .collect()
+}
+
+async fn vote(
+    app: &TestApp,
+    poll_id: &str,
+    name: &str,
+    slots: &[String],
+    available: &[bool],
+) -> String {
+    let votes: Vec<_> = slots
+        .iter()
+        .zip(available)
+        .map(|(id, available)| json!({ "time_slot_id": id, "available": available }))
+        .collect();
+    let response = app
+        .request(Method::POST, &format!("/poll/{poll_id}/vote"))
+        .json(&json!({ "participant_name": name, "votes": votes }))
+        .send()
+        .await
+        .unwrap();
+    let body = expect_json(response, 201).await;
+    body["participant_id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn signup_rejects_a_taken_name() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        assert!(!token.is_empty());
+
+        let response = app
+            .request(Method::POST, "/admin/signup")
+            .json(&json!({ "name": "alice", "password": "another long secret" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 409);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn only_one_admin_skips_the_invite_on_a_fresh_install() {
+    run_with(
+        |state| {
+            state.auth_policy = Arc::new(AuthPolicy {
+                signup_mode: SignupMode::Invite,
+                ..(*state.auth_policy).clone()
+            });
+        },
+        |app| async move {
+            let signup = |name: &str| {
+                app.request(Method::POST, "/admin/signup")
+                    .json(&json!({ "name": name, "password": "correct horse battery" }))
+                    .send()
+            };
+            let (alice, bob, carol) = tokio::join!(signup("alice"), signup("bob"), signup("carol"));
+            let mut statuses: Vec<u16> = [alice, bob, carol]
+                .into_iter()
+                .map(|response| response.unwrap().status().as_u16())
+                .collect();
+            statuses.sort();
+            assert_eq!(statuses, [200, 403, 403]);
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn creating_a_poll_needs_an_admin() {
+    run(|app| async move {
+        let response = app
+            .request(Method::POST, "/poll")
+            .json(&json!({ "title": "Team lunch", "time_slots": [] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+        assert!(response.headers().contains_key("x-request-id"));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn created_polls_can_be_read_back() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        let id = app.create_poll(&token, 3).await;
+
+        let poll = expect_json(app.get_poll(&id).await, 200).await;
+        assert_eq!(poll["id"], id.as_str());
+        assert_eq!(poll["title"], "Team lunch");
+        assert_eq!(available_counts(&poll), [0, 0, 0]);
+        assert_eq!(poll["participants"], json!([]));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn slots_must_end_after_they_start() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        for ends_at in ["2030-01-01T08:00:00Z", "2030-01-01T09:00:00Z"] {
+            let response = app
+                .admin_request(Method::POST, "/poll", &token)
+                .json(&json!({
+                    "title": "Team lunch",
+                    "time_slots": [{ "starts_at": "2030-01-01T09:00:00Z", "ends_at": ends_at }],
+                }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 422, "{ends_at}");
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn the_finalized_filter_is_refused_with_a_reason() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        app.create_poll(&token, 1).await;
+
+        let response = app
+            .admin_request(Method::GET, "/events?status=open", &token)
+            .send()
+            .await
+            .unwrap();
+        let body = expect_json(response, 200).await;
+        assert_eq!(body["events"].as_array().unwrap().len(), 1);
+
+        let response = app
+            .admin_request(Method::GET, "/events?status=finalized", &token)
+            .send()
+            .await
+            .unwrap();
+        let body = expect_json(response, 422).await;
+        assert!(
+            body["error"].as_str().unwrap().contains("finalized"),
+            "{body}"
+        );
+        assert!(body["request_id"].is_string());
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn votes_are_counted_and_can_be_changed() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        let id = app.create_poll(&token, 2).await;
+        let slots = slot_ids(&expect_json(app.get_poll(&id).await, 200).await);
+
+        let bob = vote(&app, &id, "Bob", &slots, &[true, false]).await;
+        vote(&app, &id, "Carol", &slots, &[true, true]).await;
+        let poll = expect_json(app.get_poll(&id).await, 200).await;
+        assert_eq!(available_counts(&poll), [2, 1]);
+        assert_eq!(poll["participants"].as_array().unwrap().len(), 2);
+
+        let votes: Vec<_> = slots
+            .iter()
+            .map(|id| json!({ "time_slot_id": id, "available": false }))
+            .collect();
+        let response = app
+            .request(Method::PUT, &format!("/poll/{id}/participant/{bob}"))
+            .json(&json!({ "votes": votes }))
+            .send()
+            .await
+            .unwrap();
+        expect_json(response, 200).await;
+        let poll = expect_json(app.get_poll(&id).await, 200).await;
+        assert_eq!(available_counts(&poll), [1, 1]);
+
+        let response = app
+            .admin_request(Method::GET, &format!("/poll/{id}/audit"), &token)
+            .send()
+            .await
+            .unwrap();
+        let audit = expect_json(response, 200).await;
+        let actions: Vec<_> = audit["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["action"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                "votes.updated",
+                "participant.joined",
+                "participant.joined",
+                "event.created"
+            ]
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn votes_for_another_polls_slots_are_rejected() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        let id = app.create_poll(&token, 1).await;
+        let other = app.create_poll(&token, 1).await;
+        let foreign = slot_ids(&expect_json(app.get_poll(&other).await, 200).await);
+
+        let response = app
+            .request(Method::POST, &format!("/poll/{id}/vote"))
+            .json(&json!({
+                "participant_name": "Bob",
+                "votes": [{ "time_slot_id": foreign[0], "available": true }],
+            }))
+            .send()
+            .await
+            .unwrap();
+        let body = expect_json(response, 422).await;
+        assert_eq!(body["time_slot_ids"], json!([foreign[0]]));
+        let poll = expect_json(app.get_poll(&id).await, 200).await;
+        assert_eq!(poll["participants"], json!([]));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn unchanged_polls_are_not_sent_again() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        let id = app.create_poll(&token, 2).await;
+
+        let response = app.get_poll(&id).await;
+        let etag = response.headers()["etag"].to_str().unwrap().to_string();
+        assert!(response.headers().contains_key("last-modified"));
+        let slots = slot_ids(&expect_json(response, 200).await);
+
+        let response = app
+            .request(Method::GET, &format!("/poll/{id}"))
+            .header("if-none-match", &etag)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 304);
+        assert_eq!(response.headers()["etag"], etag.as_str());
+
+        vote(&app, &id, "Bob", &slots, &[true, false]).await;
+        let response = app
+            .request(Method::GET, &format!("/poll/{id}"))
+            .header("if-none-match", &etag)
+            .send()
+            .await
+            .unwrap();
+        assert_ne!(response.headers()["etag"], etag.as_str());
+        let poll = expect_json(response, 200).await;
+        assert_eq!(available_counts(&poll), [1, 0]);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn deleted_polls_disappear_until_restored() {
+    run(|app| async move {
+        let alice = app.signup("alice").await;
+        let mallory = app.signup("mallory").await;
+        let id = app.create_poll(&alice, 1).await;
+
+        let response = app
+            .admin_request(Method::DELETE, &format!("/poll/{id}"), &mallory)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403);
+
+        let response = app
+            .admin_request(Method::DELETE, &format!("/poll/{id}"), &alice)
+            .send()
+            .await
+            .unwrap();
+        expect_json(response, 200).await;
+        assert_eq!(app.get_poll(&id).await.status(), 404);
+
+        let deleted: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM events WHERE id = $1 AND deleted_at IS NOT NULL",
+        )
+        .bind(&id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+        assert_eq!(deleted, 1, "deleting only moves the poll to the trash");
+
+        let response = app
+            .admin_request(Method::POST, &format!("/poll/{id}/restore"), &alice)
+            .send()
+            .await
+            .unwrap();
+        expect_json(response, 200).await;
+        assert_eq!(app.get_poll(&id).await.status(), 200);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn organization_changes_are_audited() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        app.signup("bob").await;
+        let response = app
+            .admin_request(Method::POST, "/organizations", &token)
+            .json(&json!({ "name": "Acme" }))
+            .send()
+            .await
+            .unwrap();
+        let organization = expect_json(response, 201).await;
+        let members = format!(
+            "/organizations/{}/members",
+            organization["id"].as_str().unwrap()
+        );
+
+        for role in ["member", "admin"] {
+            let response = app
+                .admin_request(Method::POST, &members, &token)
+                .json(&json!({ "name": "bob", "role": role }))
+                .send()
+                .await
+                .unwrap();
+            expect_json(response, 200).await;
+        }
+        let response = app
+            .admin_request(Method::GET, &members, &token)
+            .send()
+            .await
+            .unwrap();
+        let listed = expect_json(response, 200).await;
+        let bob = listed
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|member| member["name"] == "bob")
+            .unwrap();
+        let response = app
+            .admin_request(
+                Method::DELETE,
+                &format!("{members}/{}", bob["admin_id"].as_str().unwrap()),
+                &token,
+            )
+            .send()
+            .await
+            .unwrap();
+        expect_json(response, 200).await;
+
+        let mut actions: Vec<String> =
+            sqlx::query_scalar("SELECT action FROM audit_log WHERE action LIKE 'organization.%'")
+                .fetch_all(&app.pool)
+                .await
+                .unwrap();
+        actions.sort();
+        assert_eq!(
+            actions,
+            [
+                "organization.created",
+                "organization.member_added",
+                "organization.member_removed",
+                "organization.member_role_changed",
+            ]
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn organization_owners_count_as_poll_owners() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        let response = app
+            .admin_request(Method::POST, "/organizations", &token)
+            .json(&json!({ "name": "Acme" }))
+            .send()
+            .await
+            .unwrap();
+        let organization = expect_json(response, 201).await;
+        let organization_id = organization["id"].as_str().unwrap();
+        let response = app
+            .admin_request(
+                Method::GET,
+                &format!("/organizations/{organization_id}/members"),
+                &token,
+            )
+            .send()
+            .await
+            .unwrap();
+        let members = expect_json(response, 200).await;
+        let alice = members[0]["admin_id"].as_str().unwrap();
+
+        // A personal poll can't lose its only owner
+        let personal = app.create_poll(&token, 1).await;
+        let response = app
+            .admin_request(
+                Method::DELETE,
+                &format!("/poll/{personal}/collaborators/{alice}"),
+                &token,
+            )
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 409);
+
+        // An organization's poll stays owned by the organization's owners
+        let response = app
+            .admin_request(Method::POST, "/poll", &token)
+            .json(&json!({
+                "title": "Offsite",
+                "organization_id": organization_id,
+                "time_slots": [{
+                    "starts_at": "2030-01-01T09:00:00Z",
+                    "ends_at": "2030-01-01T10:00:00Z",
+                }],
+            }))
+            .send()
+            .await
+            .unwrap();
+        let shared = expect_json(response, 201).await;
+        let shared = shared["id"].as_str().unwrap();
+        let response = app
+            .admin_request(
+                Method::DELETE,
+                &format!("/poll/{shared}/collaborators/{alice}"),
+                &token,
+            )
+            .send()
+            .await
+            .unwrap();
+        expect_json(response, 200).await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn api_keys_only_take_scopes_routes_enforce() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        for (scope, status) in [("polls:create", 201), ("polls:finalize", 422)] {
+            let response = app
+                .admin_request(Method::POST, "/admin/api-keys", &token)
+                .json(&json!({ "name": "ci", "scopes": [scope] }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{scope}");
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn deleting_an_account_takes_its_polls_and_lone_organizations() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        let poll = app.create_poll(&token, 1).await;
+        let response = app
+            .admin_request(Method::POST, "/organizations", &token)
+            .json(&json!({ "name": "Acme" }))
+            .send()
+            .await
+            .unwrap();
+        let organization = expect_json(response, 201).await;
+        let response = app
+            .admin_request(Method::POST, "/poll", &token)
+            .json(&json!({
+                "title": "Offsite",
+                "organization_id": organization["id"],
+                "time_slots": [{
+                    "starts_at": "2030-01-01T09:00:00Z",
+                    "ends_at": "2030-01-01T10:00:00Z",
+                }],
+            }))
+            .send()
+            .await
+            .unwrap();
+        let team_poll = expect_json(response, 201).await;
+        let team_poll = team_poll["id"].as_str().unwrap();
+
+        let response = app
+            .admin_request(Method::DELETE, "/admin/account", &token)
+            .json(&json!({ "password": "correct horse battery", "confirm_name": "alice" }))
+            .send()
+            .await
+            .unwrap();
+        expect_json(response, 200).await;
+
+        assert_eq!(app.get_poll(&poll).await.status(), 404);
+        let organizations: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM organizations")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+        assert_eq!(organizations, 0);
+
+        // The organization's poll is in the trash rather than gone
+        assert_eq!(app.get_poll(team_poll).await.status(), 404);
+        let trashed: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM events WHERE id = $1 AND deleted_at IS NOT NULL AND organization_id IS NULL",
+        )
+        .bind(team_poll)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+        assert_eq!(trashed, 1);
+
+        let response = app
+            .request(Method::POST, "/admin/login")
+            .json(&json!({ "name": "alice", "password": "correct horse battery" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn answers_export_as_csv_with_the_export_scope() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        let id = app.create_poll(&token, 2).await;
+        let poll = expect_json(app.get_poll(&id).await, 200).await;
+        let slots = slot_ids(&poll);
+        vote(&app, &id, "Smith, Jo", &slots, &[true, false]).await;
+        vote(&app, &id, "=cmd()", &slots[..1], &[true]).await;
+
+        let mut keys = Vec::new();
+        for scope in ["polls:export", "events:read"] {
+            let response = app
+                .admin_request(Method::POST, "/admin/api-keys", &token)
+                .json(&json!({ "name": scope, "scopes": [scope] }))
+                .send()
+                .await
+                .unwrap();
+            let body = expect_json(response, 201).await;
+            keys.push(body["key"].as_str().unwrap().to_string());
+        }
+        let export = |key: &str| {
+            app.request(Method::GET, &format!("/poll/{id}/export"))
+                .bearer_auth(key)
+                .send()
+        };
+
+        assert_eq!(export(&keys[1]).await.unwrap().status(), 403);
+        let response = export(&keys[0]).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers()["content-type"],
+            "text/csv; charset=utf-8"
+        );
+        let csv = response.text().await.unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 4, "{csv}");
+        assert!(
+            lines[0].starts_with("participant,2030-01-01T09:00:00"),
+            "{csv}"
+        );
+        assert_eq!(lines[1], "\"Smith, Jo\",yes,no");
+        assert_eq!(lines[2], "'=cmd(),yes,");
+        assert_eq!(lines[3], "available,2,0");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn webhooks_to_internal_addresses_are_refused() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+
+        for url in [
+            "http://169.254.169.254/latest/meta-data/",
+            "http://127.0.0.1:8088/",
+            "http://localhost:8088/",
+            "http://10.0.0.5/hook",
+        ] {
+            let response = app
+                .admin_request(Method::POST, "/webhooks", &token)
+                .json(&json!({ "url": url, "events": ["votes.updated"] }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 422, "{url}");
+        }
+
+        let response = app
+            .admin_request(Method::POST, "/webhooks", &token)
+            .json(&json!({ "url": "https://93.184.216.34/hook", "events": ["votes.updated"] }))
+            .send()
+            .await
+            .unwrap();
+        expect_json(response, 201).await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn webhooks_only_subscribe_to_events_that_are_sent() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        for (event, status) in [("votes.updated", 201), ("poll.finalized", 422)] {
+            let response = app
+                .admin_request(Method::POST, "/webhooks", &token)
+                .json(&json!({ "url": "https://93.184.216.34/hook", "events": [event] }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{event}");
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn unversioned_paths_announce_their_deprecation() {
+    run(|app| async move {
+        let token = app.signup("alice").await;
+        let id = app.create_poll(&token, 1).await;
+
+        let response = reqwest::get(app.url(&format!("/api/poll/{id}")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.headers().contains_key("deprecation"));
+        assert_eq!(
+            response.headers()["link"],
+            format!("</api/v1/poll/{id}>; rel=\"successor-version\"").as_str()
+        );
+
+        let response = app.get_poll(&id).await;
+        assert!(!response.headers().contains_key("deprecation"));
+    })
+    .await;
+}
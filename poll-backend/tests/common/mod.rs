@@ -0,0 +1,262 @@
+//! Runs the app against throwaway databases: a Postgres schema and a SQLite
+//! file per test, migrated from scratch and removed afterwards, so tests can
+//! run in parallel against one Postgres server.
+
+// Each test binary uses its own part of these helpers
+#![allow(dead_code)]
+
+use std::{future::Future, net::SocketAddr, panic, path::PathBuf, sync::Arc, time::Duration};
+
+use metrics_exporter_prometheus::PrometheusBuilder;
+use reqwest::{Method, RequestBuilder, Response};
+use serde_json::{json, Value};
+use sqlx::{AnyConnection, AnyPool, Connection, Executor};
+use tokio::net::TcpListener;
+
+use poll_backend::{
+    app,
+    client_ip::TrustedProxies,
+    clock::SystemClock,
+    config::{AllowedOrigins, DatabaseConfig, HttpConfig},
+    db::{self, Backend},
+    password_reset::LogResetLinkSender,
+    policy::{AuthPolicy, PasswordPolicy, SignupMode},
+    repository::SqlEventRepository,
+    revision::PollCache,
+    state::AppState,
+    webhooks::AllowedNetworks,
+};
+
+/// Used when neither `TEST_DATABASE_URL` nor `DATABASE_URL` is set; matches
+/// `scripts/start-local-postgres.sh`.
+const DEFAULT_DATABASE_URL: &str = "postgres://poll@localhost:5432/poll";
+
+/// The app listening on a local port, with its own database.
+pub struct TestApp {
+    pub addr: SocketAddr,
+    pub pool: AnyPool,
+    client: reqwest::Client,
+}
+
+impl TestApp {
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr)
+    }
+
+    /// A request to `path` under `/api/v1`.
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, self.url(&format!("/api/v1{path}")))
+    }
+
+    /// Like [`Self::request`], signed in with a session token.
+    pub fn admin_request(&self, method: Method, path: &str, token: &str) -> RequestBuilder {
+        self.request(method, path).header("x-admin-token", token)
+    }
+
+    /// Signs up an admin and returns their session token.
+    pub async fn signup(&self, name: &str) -> String {
+        let response = self
+            .request(Method::POST, "/admin/signup")
+            .json(&json!({ "name": name, "password": "correct horse battery" }))
+            .send()
+            .await
+            .unwrap();
+        let body = expect_json(response, 200).await;
+        body["token"].as_str().unwrap().to_string()
+    }
+
+    /// Creates a poll with `slots` one-hour slots and returns its id.
+    pub async fn create_poll(&self, token: &str, slots: usize) -> String {
+        let time_slots: Vec<_> = (0..slots)
+            .map(|i| {
+                json!({
+                    "starts_at": format!("2030-01-01T{:02}:00:00Z", 9 + i),
+                    "ends_at": format!("2030-01-01T{:02}:00:00Z", 10 + i),
+                })
+            })
+            .collect();
+        let response = self
+            .admin_request(Method::POST, "/poll", token)
+            .json(&json!({ "title": "Team lunch", "time_slots": time_slots }))
+            .send()
+            .await
+            .unwrap();
+        let body = expect_json(response, 201).await;
+        body["id"].as_str().unwrap().to_string()
+    }
+
+    pub async fn get_poll(&self, id: &str) -> Response {
+        self.request(Method::GET, &format!("/poll/{id}"))
+            .send()
+            .await
+            .unwrap()
+    }
+}
+
+/// Asserts the status and returns the JSON body.
+pub async fn expect_json(response: Response, status: u16) -> Value {
+    let actual = response.status().as_u16();
+    let body = response.text().await.unwrap();
+    assert_eq!(actual, status, "unexpected status, body: {body}");
+    serde_json::from_str(&body).unwrap_or(Value::Null)
+}
+
+/// Runs `test` against a fresh app on each backend and removes its database
+/// afterwards, also when the test fails.
+pub async fn run<F, Fut>(test: F)
+where
+    F: Fn(TestApp) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    run_with(|_| {}, test).await
+}
+
+/// Like [`run`], letting `configure` change the app's state first.
+pub async fn run_with<C, F, Fut>(configure: C, test: F)
+where
+    C: Fn(&mut AppState),
+    F: Fn(TestApp) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    for backend in [Backend::Postgres, Backend::Sqlite] {
+        let scratch = Scratch::create(backend).await;
+        let pool = db::connect(&DatabaseConfig {
+            url: scratch.url(),
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(10),
+            idle_timeout: Duration::from_secs(60),
+        })
+        .await
+        .unwrap();
+        db::migrate(&pool).await.unwrap();
+
+        let app = spawn(pool.clone(), &configure).await;
+        let outcome = tokio::spawn(test(app)).await;
+
+        pool.close().await;
+        scratch.remove().await;
+
+        if let Err(e) = outcome {
+            eprintln!("failed on {backend:?}");
+            panic::resume_unwind(e.into_panic());
+        }
+    }
+}
+
+/// A database that only lives for one test.
+enum Scratch {
+    Postgres {
+        url: String,
+        schema: String,
+        admin: AnyConnection,
+    },
+    Sqlite {
+        path: PathBuf,
+    },
+}
+
+impl Scratch {
+    async fn create(backend: Backend) -> Self {
+        let name = format!("test_{}", uuid::Uuid::new_v4().simple());
+        match backend {
+            Backend::Postgres => {
+                sqlx::any::install_default_drivers();
+                let url = std::env::var("TEST_DATABASE_URL")
+                    .or_else(|_| std::env::var("DATABASE_URL"))
+                    .unwrap_or_else(|_| DEFAULT_DATABASE_URL.to_string());
+                let mut admin = AnyConnection::connect(&url)
+                    .await
+                    .unwrap_or_else(|e| panic!("integration tests need Postgres at {url}: {e}"));
+                admin
+                    .execute(format!("CREATE SCHEMA {name}").as_str())
+                    .await
+                    .unwrap();
+                Self::Postgres {
+                    url,
+                    schema: name,
+                    admin,
+                }
+            }
+            Backend::Sqlite => Self::Sqlite {
+                path: std::env::temp_dir().join(format!("poll_{name}.db")),
+            },
+        }
+    }
+
+    fn url(&self) -> String {
+        match self {
+            Self::Postgres { url, schema, .. } => {
+                let separator = if url.contains('?') { '&' } else { '?' };
+                format!("{url}{separator}options=-c%20search_path%3D{schema}")
+            }
+            Self::Sqlite { path } => format!("sqlite://{}", path.display()),
+        }
+    }
+
+    async fn remove(self) {
+        match self {
+            Self::Postgres {
+                schema, mut admin, ..
+            } => {
+                admin
+                    .execute(format!("DROP SCHEMA {schema} CASCADE").as_str())
+                    .await
+                    .unwrap();
+            }
+            Self::Sqlite { path } => {
+                for suffix in ["", "-wal", "-shm"] {
+                    let mut file = path.clone().into_os_string();
+                    file.push(suffix);
+                    std::fs::remove_file(file).ok();
+                }
+            }
+        }
+    }
+}
+
+async fn spawn(pool: AnyPool, configure: impl Fn(&mut AppState)) -> TestApp {
+    let mut state = AppState {
+        pool: pool.clone(),
+        events: Arc::new(SqlEventRepository::new(pool.clone())),
+        reset_sender: Arc::new(LogResetLinkSender),
+        auth_policy: Arc::new(AuthPolicy {
+            signup_mode: SignupMode::Open,
+            password: PasswordPolicy::default(),
+            secure_cookies: false,
+        }),
+        public_base_url: "http://localhost:5173".to_string(),
+        clock: Arc::new(SystemClock),
+        oidc: None,
+        trusted_proxies: Arc::new(TrustedProxies(Vec::new())),
+        webhook_networks: Arc::new(AllowedNetworks::default()),
+        poll_cache: Arc::new(PollCache::new(100)),
+        metrics: PrometheusBuilder::new().build_recorder().handle(),
+    };
+    configure(&mut state);
+    let http = HttpConfig {
+        allowed_origins: AllowedOrigins::List(Vec::new()),
+        body_limit: 2 * 1024 * 1024,
+        trusted_proxies: Vec::new(),
+        legacy_api_sunset: None,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = app(state, &http);
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+
+    TestApp {
+        addr,
+        pool,
+        client: reqwest::Client::new(),
+    }
+}
@@ -0,0 +1,398 @@
+//! Signing in and linking identities through an OpenID Connect provider,
+//! played by a mock issuer.
+
+mod common;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::{extract::State, routing::get, routing::post, Json, Router};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use reqwest::{Method, Response};
+use serde_json::{json, Value};
+use url::Url;
+
+use common::{expect_json, run_with, TestApp};
+use poll_backend::clock::{Clock, FixedClock};
+use poll_backend::oidc::{OidcClient, OidcConfig};
+use poll_backend::policy::{AuthPolicy, SignupMode};
+use poll_backend::totp;
+
+const CLIENT_ID: &str = "good-times";
+
+/// The issuer's URL and the nonce of the login it last saw.
+#[derive(Default)]
+struct Issuer {
+    url: String,
+    nonce: String,
+}
+
+type Shared = Arc<Mutex<Issuer>>;
+
+async fn discovery(State(issuer): State<Shared>) -> Json<Value> {
+    let url = issuer.lock().unwrap().url.clone();
+    Json(json!({
+        "issuer": url,
+        "authorization_endpoint": format!("{url}/authorize"),
+        "token_endpoint": format!("{url}/token"),
+    }))
+}
+
+async fn token(State(issuer): State<Shared>) -> Json<Value> {
+    let issuer = issuer.lock().unwrap();
+    let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#);
+    let claims = URL_SAFE_NO_PAD.encode(
+        json!({
+            "iss": issuer.url,
+            "sub": "user-1",
+            "aud": [CLIENT_ID],
+            "exp": 4_000_000_000i64,
+            "nonce": issuer.nonce,
+            "preferred_username": "jo",
+        })
+        .to_string(),
+    );
+    Json(json!({ "id_token": format!("{header}.{claims}."), "token_type": "Bearer" }))
+}
+
+async fn start_issuer() -> Shared {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let issuer: Shared = Arc::new(Mutex::new(Issuer {
+        url: format!("http://{}", listener.local_addr().unwrap()),
+        ..Default::default()
+    }));
+    let app = Router::new()
+        .route("/.well-known/openid-configuration", get(discovery))
+        .route("/token", post(token))
+        .with_state(issuer.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    issuer
+}
+
+fn oidc_client(issuer: &Shared) -> Arc<OidcClient> {
+    Arc::new(OidcClient::new(OidcConfig {
+        issuer_url: issuer.lock().unwrap().url.clone(),
+        client_id: CLIENT_ID.into(),
+        client_secret: None,
+        redirect_url: "http://localhost/api/v1/admin/oidc/callback".into(),
+        scopes: "openid profile".into(),
+        allow_signup: true,
+    }))
+}
+
+/// A browser that doesn't follow redirects, so they can be inspected.
+fn browser() -> reqwest::Client {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap()
+}
+
+/// `name=value` from the response's `Set-Cookie` headers.
+fn set_cookie(response: &Response, name: &str) -> Option<String> {
+    response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .map(|value| value.split(';').next().unwrap().to_string())
+        .find(|pair| pair.starts_with(&format!("{name}=")))
+}
+
+/// Plays the provider's part of the redirect: remembers the nonce the login
+/// URL asked for and returns its state.
+fn authorize(issuer: &Shared, authorization_url: &str) -> String {
+    let query: HashMap<_, _> = Url::parse(authorization_url)
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect();
+    issuer.lock().unwrap().nonce = query["nonce"].clone();
+    query["state"].clone()
+}
+
+/// Sends the provider's redirect back to the app and returns where the app
+/// sends the browser next.
+async fn callback(app: &TestApp, state: &str, cookie: Option<&str>) -> Response {
+    let mut request = browser().get(app.url(&format!(
+        "/api/v1/admin/oidc/callback?code=code-1&state={state}"
+    )));
+    if let Some(cookie) = cookie {
+        request = request.header("cookie", cookie);
+    }
+    let response = request.send().await.unwrap();
+    assert_eq!(response.status(), 303);
+    response
+}
+
+fn location(response: &Response) -> &str {
+    response.headers()["location"].to_str().unwrap()
+}
+
+/// Signs in through the provider from start to finish and returns the
+/// session token.
+async fn provider_login(app: &TestApp, issuer: &Shared) -> String {
+    let response = browser()
+        .get(app.url("/api/v1/admin/oidc/login"))
+        .send()
+        .await
+        .unwrap();
+    let binding = set_cookie(&response, "oidc_binding").unwrap();
+    let state = authorize(issuer, location(&response));
+    let response = callback(app, &state, Some(&binding)).await;
+    let session = set_cookie(&response, "admin_session").unwrap();
+    session.split_once('=').unwrap().1.to_string()
+}
+
+#[tokio::test]
+async fn logins_only_complete_in_the_browser_that_started_them() {
+    let issuer = start_issuer().await;
+    let client = oidc_client(&issuer);
+    run_with(
+        |state| state.oidc = Some(client.clone()),
+        |app| {
+            let issuer = issuer.clone();
+            async move {
+                let response = browser()
+                    .get(app.url("/api/v1/admin/oidc/login"))
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), 303);
+                let binding = set_cookie(&response, "oidc_binding").unwrap();
+                let state = authorize(&issuer, location(&response));
+
+                let response = callback(&app, &state, None).await;
+                assert!(location(&response).ends_with("#oidc_error=invalid_state"));
+                let response = callback(&app, &state, Some("oidc_binding=forged")).await;
+                assert!(location(&response).ends_with("#oidc_error=invalid_state"));
+
+                let response = callback(&app, &state, Some(&binding)).await;
+                assert!(location(&response).ends_with("#oidc_name=jo"));
+                assert!(set_cookie(&response, "admin_session").is_some());
+                assert_eq!(set_cookie(&response, "oidc_binding").unwrap(), "oidc_binding=");
+            }
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn identities_are_only_linked_from_the_linking_browser() {
+    let issuer = start_issuer().await;
+    let client = oidc_client(&issuer);
+    run_with(
+        |state| state.oidc = Some(client.clone()),
+        |app| {
+            let issuer = issuer.clone();
+            async move {
+                let token = app.signup("alice").await;
+                let response = app
+                    .admin_request(Method::POST, "/admin/oidc/link", &token)
+                    .send()
+                    .await
+                    .unwrap();
+                let binding = set_cookie(&response, "oidc_binding").unwrap();
+                let body = expect_json(response, 200).await;
+                let state = authorize(&issuer, body["authorization_url"].as_str().unwrap());
+
+                // Someone else's browser, sent the link by alice
+                let response = callback(&app, &state, None).await;
+                assert!(location(&response).ends_with("#oidc_error=invalid_state"));
+                let linked: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM admin_identities")
+                    .fetch_one(&app.pool)
+                    .await
+                    .unwrap();
+                assert_eq!(linked, 0);
+
+                let response = callback(&app, &state, Some(&binding)).await;
+                assert!(location(&response).ends_with("#oidc_linked=1"));
+            }
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn provider_logins_still_need_the_second_factor() {
+    let issuer = start_issuer().await;
+    let client = oidc_client(&issuer);
+    let clock = Arc::new(FixedClock::new(chrono::Utc::now()));
+    run_with(
+        |state| {
+            state.oidc = Some(client.clone());
+            state.clock = clock.clone();
+        },
+        |app| {
+            let issuer = issuer.clone();
+            let clock = clock.clone();
+            async move {
+                let token = app.signup("alice").await;
+                let response = app
+                    .admin_request(Method::POST, "/admin/2fa/setup", &token)
+                    .send()
+                    .await
+                    .unwrap();
+                let setup = expect_json(response, 200).await;
+                let secret = totp::decode_secret(setup["secret"].as_str().unwrap()).unwrap();
+                let response = app
+                    .admin_request(Method::POST, "/admin/2fa/enable", &token)
+                    .json(&json!({ "code": totp::code_at(&secret, clock.now()) }))
+                    .send()
+                    .await
+                    .unwrap();
+                expect_json(response, 200).await;
+
+                let response = app
+                    .admin_request(Method::POST, "/admin/oidc/link", &token)
+                    .send()
+                    .await
+                    .unwrap();
+                let binding = set_cookie(&response, "oidc_binding").unwrap();
+                let body = expect_json(response, 200).await;
+                let state = authorize(&issuer, body["authorization_url"].as_str().unwrap());
+                let response = callback(&app, &state, Some(&binding)).await;
+                assert!(location(&response).ends_with("#oidc_linked=1"));
+
+                let response = browser()
+                    .get(app.url("/api/v1/admin/oidc/login"))
+                    .send()
+                    .await
+                    .unwrap();
+                let binding = set_cookie(&response, "oidc_binding").unwrap();
+                let state = authorize(&issuer, location(&response));
+                let response = callback(&app, &state, Some(&binding)).await;
+                assert!(set_cookie(&response, "admin_session").is_none());
+                let (_, challenge) = location(&response).split_once("#oidc_challenge=").unwrap();
+
+                clock.advance(chrono::Duration::seconds(30));
+                let response = app
+                    .request(Method::POST, "/admin/login/2fa")
+                    .json(&json!({
+                        "challenge_token": challenge,
+                        "code": totp::code_at(&secret, clock.now()),
+                    }))
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), 200);
+                assert!(set_cookie(&response, "admin_session").is_some());
+            }
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn admins_without_a_password_confirm_with_a_recent_login() {
+    let issuer = start_issuer().await;
+    let client = oidc_client(&issuer);
+    run_with(
+        |state| state.oidc = Some(client.clone()),
+        |app| {
+            let issuer = issuer.clone();
+            async move {
+                let token = provider_login(&app, &issuer).await;
+
+                let change = |body: Value| {
+                    app.admin_request(Method::POST, "/admin/password", &token)
+                        .json(&body)
+                        .send()
+                };
+                let response = change(json!({ "new_password": "correct horse battery" }))
+                    .await
+                    .unwrap();
+                expect_json(response, 200).await;
+
+                // With a password set, it is needed again
+                let response = change(json!({ "new_password": "another horse battery" }))
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), 401);
+                let response = change(json!({
+                    "current_password": "correct horse battery",
+                    "new_password": "another horse battery",
+                }))
+                .await
+                .unwrap();
+                expect_json(response, 200).await;
+            }
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn stale_sessions_without_a_password_cannot_delete_the_account() {
+    let issuer = start_issuer().await;
+    let client = oidc_client(&issuer);
+    run_with(
+        |state| state.oidc = Some(client.clone()),
+        |app| {
+            let issuer = issuer.clone();
+            async move {
+                let token = provider_login(&app, &issuer).await;
+
+                let an_hour_ago = (chrono::Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+                sqlx::query("UPDATE admin_sessions SET created_at = $1")
+                    .bind(an_hour_ago)
+                    .execute(&app.pool)
+                    .await
+                    .unwrap();
+                let delete = || {
+                    app.admin_request(Method::DELETE, "/admin/account", &token)
+                        .json(&json!({ "confirm_name": "jo" }))
+                        .send()
+                };
+                assert_eq!(delete().await.unwrap().status(), 401);
+
+                sqlx::query("UPDATE admin_sessions SET created_at = $1")
+                    .bind(chrono::Utc::now().to_rfc3339())
+                    .execute(&app.pool)
+                    .await
+                    .unwrap();
+                expect_json(delete().await.unwrap(), 200).await;
+            }
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn provider_signups_follow_the_signup_mode() {
+    let issuer = start_issuer().await;
+    let client = oidc_client(&issuer);
+    for signup_mode in [SignupMode::Invite, SignupMode::Disabled] {
+        run_with(
+            |state| {
+                state.oidc = Some(client.clone());
+                state.auth_policy = Arc::new(AuthPolicy {
+                    signup_mode,
+                    ..(*state.auth_policy).clone()
+                });
+            },
+            |app| {
+                let issuer = issuer.clone();
+                async move {
+                    let response = browser()
+                        .get(app.url("/api/v1/admin/oidc/login"))
+                        .send()
+                        .await
+                        .unwrap();
+                    let binding = set_cookie(&response, "oidc_binding").unwrap();
+                    let state = authorize(&issuer, location(&response));
+
+                    let response = callback(&app, &state, Some(&binding)).await;
+                    assert!(location(&response).ends_with("#oidc_error=signup_closed"));
+                    assert!(set_cookie(&response, "admin_session").is_none());
+                    let admins: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM admins")
+                        .fetch_one(&app.pool)
+                        .await
+                        .unwrap();
+                    assert_eq!(admins, 0);
+                }
+            },
+        )
+        .await;
+    }
+}
@@ -0,0 +1,161 @@
+//! Logging in with a password and an authenticator code, on a clock the
+//! tests move by hand.
+
+mod common;
+
+use std::sync::Arc;
+
+use chrono::Duration;
+use reqwest::Method;
+use serde_json::{json, Value};
+
+use common::{expect_json, run_with, TestApp};
+use poll_backend::clock::{Clock, FixedClock};
+use poll_backend::totp;
+
+const PASSWORD: &str = "correct horse battery";
+
+/// Signs up `name` with two-factor authentication enabled and returns the
+/// TOTP secret.
+async fn enroll(app: &TestApp, clock: &FixedClock, name: &str) -> Vec<u8> {
+    let token = app.signup(name).await;
+    let response = app
+        .admin_request(Method::POST, "/admin/2fa/setup", &token)
+        .send()
+        .await
+        .unwrap();
+    let setup = expect_json(response, 200).await;
+    let secret = totp::decode_secret(setup["secret"].as_str().unwrap()).unwrap();
+
+    let response = app
+        .admin_request(Method::POST, "/admin/2fa/enable", &token)
+        .json(&json!({ "code": totp::code_at(&secret, clock.now()) }))
+        .send()
+        .await
+        .unwrap();
+    expect_json(response, 200).await;
+    secret
+}
+
+/// The first login step; returns the challenge token.
+async fn login(app: &TestApp, name: &str) -> String {
+    let response = app
+        .request(Method::POST, "/admin/login")
+        .json(&json!({ "name": name, "password": PASSWORD }))
+        .send()
+        .await
+        .unwrap();
+    let body = expect_json(response, 200).await;
+    assert_eq!(body["two_factor_required"], true);
+    body["challenge_token"].as_str().unwrap().to_string()
+}
+
+/// The second login step; returns the status.
+async fn verify(app: &TestApp, challenge: &str, code: &str) -> u16 {
+    app.request(Method::POST, "/admin/login/2fa")
+        .json(&json!({ "challenge_token": challenge, "code": code }))
+        .send()
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+/// A six-digit code that isn't the current one.
+fn wrong_code(secret: &[u8], clock: &FixedClock) -> String {
+    let right: u32 = totp::code_at(secret, clock.now()).parse().unwrap();
+    format!("{:06}", (right + 1) % 1_000_000)
+}
+
+#[tokio::test]
+async fn logs_in_with_password_then_code() {
+    let clock = Arc::new(FixedClock::new(chrono::Utc::now()));
+    run_with(
+        |state| state.clock = clock.clone(),
+        |app| {
+            let clock = clock.clone();
+            async move {
+                let secret = enroll(&app, &clock, "alice").await;
+                // The enrollment code's step can't be replayed
+                clock.advance(Duration::seconds(30));
+
+                let challenge = login(&app, "alice").await;
+                assert_eq!(verify(&app, &challenge, &wrong_code(&secret, &clock)).await, 401);
+
+                let response = app
+                    .request(Method::POST, "/admin/login/2fa")
+                    .json(&json!({
+                        "challenge_token": challenge,
+                        "code": totp::code_at(&secret, clock.now()),
+                    }))
+                    .send()
+                    .await
+                    .unwrap();
+                let session: Value = expect_json(response, 200).await;
+                assert_eq!(session["name"], "alice");
+
+                // The challenge is used up
+                clock.advance(Duration::seconds(30));
+                let code = totp::code_at(&secret, clock.now());
+                assert_eq!(verify(&app, &challenge, &code).await, 401);
+            }
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn a_new_login_ends_the_previous_challenge() {
+    let clock = Arc::new(FixedClock::new(chrono::Utc::now()));
+    run_with(
+        |state| state.clock = clock.clone(),
+        |app| {
+            let clock = clock.clone();
+            async move {
+                let secret = enroll(&app, &clock, "alice").await;
+                clock.advance(Duration::seconds(30));
+
+                let first = login(&app, "alice").await;
+                let second = login(&app, "alice").await;
+                let code = totp::code_at(&secret, clock.now());
+                assert_eq!(verify(&app, &first, &code).await, 401);
+                assert_eq!(verify(&app, &second, &code).await, 200);
+            }
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn wrong_codes_lock_the_second_step_across_challenges() {
+    let clock = Arc::new(FixedClock::new(chrono::Utc::now()));
+    run_with(
+        |state| state.clock = clock.clone(),
+        |app| {
+            let clock = clock.clone();
+            async move {
+                let secret = enroll(&app, &clock, "alice").await;
+                clock.advance(Duration::seconds(30));
+
+                // Fresh challenges don't bring fresh tries
+                for _ in 0..2 {
+                    let challenge = login(&app, "alice").await;
+                    for _ in 0..5 {
+                        let code = wrong_code(&secret, &clock);
+                        assert_eq!(verify(&app, &challenge, &code).await, 401);
+                    }
+                }
+
+                let challenge = login(&app, "alice").await;
+                let code = totp::code_at(&secret, clock.now());
+                assert_eq!(verify(&app, &challenge, &code).await, 429);
+
+                clock.advance(Duration::minutes(16));
+                let challenge = login(&app, "alice").await;
+                let code = totp::code_at(&secret, clock.now());
+                assert_eq!(verify(&app, &challenge, &code).await, 200);
+            }
+        },
+    )
+    .await;
+}
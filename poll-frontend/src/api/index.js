@@ -1,7 +1,19 @@
-const BASE = '/api'
+const BASE = '/api/v1'
 
-function getAdminToken() {
-  return localStorage.getItem('adminToken') || ''
+/** The session itself lives in an HttpOnly cookie; this one is readable for CSRF protection */
+function getCsrfToken() {
+  const match = document.cookie.match(/(?:^|;\s*)csrf_token=([^;]*)/)
+  return match ? decodeURIComponent(match[1]) : ''
+}
+
+/** Error bodies carry the request id, which the server logs under too */
+async function responseError(res) {
+  const text = await res.text().catch(() => res.statusText)
+  const err = new Error(`${res.status}: ${text}`)
+  err.status = res.status
+  err.body = text
+  err.requestId = res.headers.get('X-Request-Id')
+  return err
 }
 
 async function request(method, path, body) {
@@ -11,21 +23,14 @@ async function request(method, path, body) {
     body: body ? JSON.stringify(body) : undefined,
   })
 
-  if (!res.ok) {
-    const text = await res.text().catch(() => res.statusText)
-    const err = new Error(`${res.status}: ${text}`)
-    err.status = res.status
-    err.body = text
-    throw err
-  }
+  if (!res.ok) throw await responseError(res)
 
   return res.json()
 }
 
 async function adminRequest(method, path, body) {
-  const token = getAdminToken()
   const headers = body ? { 'Content-Type': 'application/json' } : {}
-  if (token) headers['X-Admin-Token'] = token
+  if (method !== 'GET') headers['X-CSRF-Token'] = getCsrfToken()
 
   const res = await fetch(`${BASE}${path}`, {
     method,
@@ -33,13 +38,7 @@ async function adminRequest(method, path, body) {
     body: body ? JSON.stringify(body) : undefined,
   })
 
-  if (!res.ok) {
-    const text = await res.text().catch(() => res.statusText)
-    const err = new Error(`${res.status}: ${text}`)
-    err.status = res.status
-    err.body = text
-    throw err
-  }
+  if (!res.ok) throw await responseError(res)
 
   return res.json()
 }
@@ -55,9 +54,17 @@ export const api = {
     return adminRequest('POST', '/poll', payload)
   },
 
-  /** List all events (admin) */
-  listEvents() {
-    return adminRequest('GET', '/events')
+  /**
+   * List events, newest first (admin). Accepts { cursor, limit, q, status, from, to }.
+   * Returns { events, next_cursor }
+   */
+  listEvents(filters = {}) {
+    const params = new URLSearchParams()
+    for (const [key, value] of Object.entries(filters)) {
+      if (value != null && value !== '') params.set(key, value)
+    }
+    const query = params.toString()
+    return adminRequest('GET', `/events${query ? `?${query}` : ''}`)
   },
 
   /** Delete a poll (admin). Returns { id } */
@@ -65,6 +72,51 @@ export const api = {
     return adminRequest('DELETE', `/poll/${id}`)
   },
 
+  /** Restore a deleted poll before it is purged (admin). Returns { id } */
+  restorePoll(id) {
+    return adminRequest('POST', `/poll/${id}/restore`)
+  },
+
+  /** Copy a poll, shifting every slot by offset_minutes (admin). Returns { id } */
+  duplicatePoll(id, payload = {}) {
+    return adminRequest('POST', `/poll/${id}/duplicate`, payload)
+  },
+
+  /** Save a poll's slot layout as a named template (admin). Returns the template */
+  saveTemplate(id, name) {
+    return adminRequest('POST', `/poll/${id}/template`, { name })
+  },
+
+  /** List saved templates (admin) */
+  listTemplates() {
+    return adminRequest('GET', '/templates')
+  },
+
+  /** Delete a template (admin). Returns { id } */
+  deleteTemplate(templateId) {
+    return adminRequest('DELETE', `/templates/${templateId}`)
+  },
+
+  /** Create a poll from a template starting at first_slot_starts_at (admin). Returns { id } */
+  createPollFromTemplate(templateId, payload) {
+    return adminRequest('POST', `/templates/${templateId}/poll`, payload)
+  },
+
+  /** List a poll's collaborators and their roles (admin) */
+  listCollaborators(pollId) {
+    return adminRequest('GET', `/poll/${pollId}/collaborators`)
+  },
+
+  /** Add an admin as owner, editor or viewer, or change their role (admin) */
+  addCollaborator(pollId, payload) {
+    return adminRequest('POST', `/poll/${pollId}/collaborators`, payload)
+  },
+
+  /** Remove a collaborator (admin). Returns { admin_id } */
+  removeCollaborator(pollId, adminId) {
+    return adminRequest('DELETE', `/poll/${pollId}/collaborators/${adminId}`)
+  },
+
   /** Submit votes for a participant. Returns { participant_id } */
   submitVote(pollId, payload) {
     return request('POST', `/poll/${pollId}/vote`, payload)
@@ -75,18 +127,175 @@ export const api = {
     return request('PUT', `/poll/${pollId}/participant/${participantId}`, payload)
   },
 
+  /** Remove a participant and their votes (admin). Returns { participant_id, time_slots } */
+  removeParticipant(pollId, participantId) {
+    return adminRequest('DELETE', `/poll/${pollId}/participant/${participantId}`)
+  },
+
+  /** Read a poll's audit log, newest first (admin). Returns { entries, next_cursor } */
+  getAuditLog(pollId, { before, limit } = {}) {
+    const params = new URLSearchParams()
+    if (before != null) params.set('before', before)
+    if (limit != null) params.set('limit', limit)
+    const query = params.toString()
+    return adminRequest('GET', `/poll/${pollId}/audit${query ? `?${query}` : ''}`)
+  },
+
+  /** List organizations the admin belongs to, with their role */
+  listOrganizations() {
+    return adminRequest('GET', '/organizations')
+  },
+
+  /** Create an organization owned by the admin. Returns { id, name, role, created_at } */
+  createOrganization(name) {
+    return adminRequest('POST', '/organizations', { name })
+  },
+
+  /** List an organization's members */
+  listMembers(organizationId) {
+    return adminRequest('GET', `/organizations/${organizationId}/members`)
+  },
+
+  /** Add a member or change their role. Accepts { name, role } */
+  addMember(organizationId, payload) {
+    return adminRequest('POST', `/organizations/${organizationId}/members`, payload)
+  },
+
+  /** Remove a member, or leave the organization. Returns { admin_id } */
+  removeMember(organizationId, adminId) {
+    return adminRequest('DELETE', `/organizations/${organizationId}/members/${adminId}`)
+  },
+
   /** Admin signup. Returns { token, admin_id, name } */
   signupAdmin(payload) {
     return request('POST', '/admin/signup', payload)
   },
 
-  /** Admin login. Returns { token, admin_id, name } */
+  /** Signup mode and password rules. Returns { signup_mode, password_min_length, ... } */
+  getAuthConfig() {
+    return request('GET', '/admin/auth-config')
+  },
+
+  /** Issue a single-use signup invite code (admin). Returns { code, expires_at } */
+  createInvite() {
+    return adminRequest('POST', '/admin/invites')
+  },
+
+  /**
+   * Admin login. Returns { token, admin_id, name }, or
+   * { two_factor_required, challenge_token, expires_at } when 2FA is enabled
+   */
   loginAdmin(payload) {
     return request('POST', '/admin/login', payload)
   },
 
+  /** Second login step. Accepts { challenge_token, code } or { challenge_token, recovery_code } */
+  completeLogin(payload) {
+    return request('POST', '/admin/login/2fa', payload)
+  },
+
   /** Admin logout. Returns { ok } */
   logoutAdmin() {
     return adminRequest('POST', '/admin/logout')
   },
+
+  /** Change the signed-in admin's password; other sessions are signed out. Returns { ok } */
+  changePassword(payload) {
+    return adminRequest('POST', '/admin/password', payload)
+  },
+
+  /** Ask for a password reset link. Returns { ok } whether or not the name exists */
+  requestPasswordReset(name) {
+    return request('POST', '/admin/password-reset', { name })
+  },
+
+  /** Set a new password from a reset token. Returns { ok } */
+  confirmPasswordReset(payload) {
+    return request('POST', '/admin/password-reset/confirm', payload)
+  },
+
+  /** URL that starts a sign-in with the configured OpenID Connect provider */
+  oidcLoginUrl() {
+    return `${BASE}/admin/oidc/login`
+  },
+
+  /** Link an OpenID Connect identity to the signed-in admin. Returns { authorization_url } */
+  linkOidc() {
+    return adminRequest('POST', '/admin/oidc/link')
+  },
+
+  /** Unlink every OpenID Connect identity from the signed-in admin. Returns { ok } */
+  unlinkOidc() {
+    return adminRequest('DELETE', '/admin/oidc/link')
+  },
+
+  /** Two-factor status. Returns { enabled, recovery_codes_left } */
+  getTwoFactorStatus() {
+    return adminRequest('GET', '/admin/2fa')
+  },
+
+  /** Start TOTP enrollment. Returns { secret, provisioning_uri } */
+  setupTotp() {
+    return adminRequest('POST', '/admin/2fa/setup')
+  },
+
+  /** Confirm enrollment with a first code. Returns { recovery_codes } */
+  enableTotp(code) {
+    return adminRequest('POST', '/admin/2fa/enable', { code })
+  },
+
+  /** Replace the recovery codes. Returns { recovery_codes } */
+  regenerateRecoveryCodes(code) {
+    return adminRequest('POST', '/admin/2fa/recovery-codes', { code })
+  },
+
+  /** Turn 2FA off. Accepts { password, code } or { password, recovery_code } */
+  disableTotp(payload) {
+    return adminRequest('POST', '/admin/2fa/disable', payload)
+  },
+
+  /** The signed-in admin's API keys, without their secrets */
+  listApiKeys() {
+    return adminRequest('GET', '/admin/api-keys')
+  },
+
+  /** Issue an API key. Accepts { name, scopes, expires_at? }; the response carries the `key` once */
+  createApiKey(payload) {
+    return adminRequest('POST', '/admin/api-keys', payload)
+  },
+
+  /** Revoke an API key. Returns { id } */
+  deleteApiKey(id) {
+    return adminRequest('DELETE', `/admin/api-keys/${id}`)
+  },
+
+  /** The signed-in admin's webhooks, without their secrets */
+  listWebhooks() {
+    return adminRequest('GET', '/webhooks')
+  },
+
+  /** Register a webhook. Accepts { url, events, event_id? }; the response carries the `secret` once */
+  createWebhook(payload) {
+    return adminRequest('POST', '/webhooks', payload)
+  },
+
+  /** Remove a webhook. Returns { id } */
+  deleteWebhook(id) {
+    return adminRequest('DELETE', `/webhooks/${id}`)
+  },
+
+  /** Recent deliveries of a webhook, newest first */
+  listWebhookDeliveries(id) {
+    return adminRequest('GET', `/webhooks/${id}/deliveries`)
+  },
+
+  /** Queue a test delivery. Returns { delivery_id } */
+  pingWebhook(id) {
+    return adminRequest('POST', `/webhooks/${id}/ping`)
+  },
+
+  /** Delete the signed-in admin and their polls. Accepts { password, confirm_name } */
+  deleteAccount(payload) {
+    return adminRequest('DELETE', '/admin/account', payload)
+  },
 }
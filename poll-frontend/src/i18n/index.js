@@ -27,9 +27,16 @@ const messages = {
       logout: 'Log out',
       goToAdmin: 'Go to admin',
       signedInAs: 'Signed in as {name}',
+      twoFactorTitle: 'Two-step verification',
+      codePlaceholder: 'Authenticator or recovery code',
+      verify: 'Verify',
+      sso: 'Sign in with your organization',
       errors: {
         required: 'Please enter a name and password.',
         invalidCredentials: 'Wrong username or password',
+        codeRequired: 'Please enter a code.',
+        invalidCode: 'That code did not work. Log in again if it keeps failing.',
+        sso: 'Signing in with your organization failed ({code}).',
       },
     },
     admin: {
@@ -117,9 +124,16 @@ const messages = {
       logout: 'Logga ut',
       goToAdmin: 'Gå till admin',
       signedInAs: 'Inloggad som {name}',
+      twoFactorTitle: 'Tvåstegsverifiering',
+      codePlaceholder: 'Kod från appen eller återställningskod',
+      verify: 'Verifiera',
+      sso: 'Logga in via din organisation',
       errors: {
         required: 'Skriv in namn och lösenord.',
         invalidCredentials: 'Fel användarnamn eller lösenord',
+        codeRequired: 'Skriv in en kod.',
+        invalidCode: 'Koden fungerade inte. Logga in igen om det fortsätter.',
+        sso: 'Inloggningen via din organisation misslyckades ({code}).',
       },
     },
     admin: {
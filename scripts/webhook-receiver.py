@@ -0,0 +1,54 @@
+#!/usr/bin/env python3
+"""Prints webhook deliveries and checks their signatures.
+
+Usage: WEBHOOK_SECRET=whsec_... ./scripts/webhook-receiver.py [port]
+
+Register http://localhost:<port>/ as the webhook URL. Set FAIL=1 to answer
+every delivery with 500 and watch the backend retry.
+"""
+
+import hashlib
+import hmac
+import json
+import os
+import sys
+import time
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+SECRET = os.environ.get("WEBHOOK_SECRET", "")
+FAIL = os.environ.get("FAIL") == "1"
+# Deliveries signed longer ago than this are treated as replays
+TOLERANCE_SECONDS = 5 * 60
+
+
+class Receiver(BaseHTTPRequestHandler):
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers.get("Content-Length", 0)))
+        timestamp = self.headers.get("x-webhook-timestamp", "")
+        signature = self.headers.get("x-webhook-signature", "")
+
+        expected = "sha256=" + hmac.new(
+            SECRET.encode(), timestamp.encode() + b"." + body, hashlib.sha256
+        ).hexdigest()
+        fresh = timestamp.isdigit() and abs(time.time() - int(timestamp)) <= TOLERANCE_SECONDS
+        valid = bool(SECRET) and fresh and hmac.compare_digest(expected, signature)
+
+        print(
+            f"{self.headers.get('x-webhook-event')} "
+            f"delivery={self.headers.get('x-webhook-delivery')} "
+            f"signature={'ok' if valid else 'INVALID'}"
+        )
+        print(json.dumps(json.loads(body), indent=2), flush=True)
+
+        status = 500 if FAIL else (204 if valid else 401)
+        self.send_response(status)
+        self.end_headers()
+
+    def log_message(self, *args):
+        pass
+
+
+if __name__ == "__main__":
+    port = int(sys.argv[1]) if len(sys.argv) > 1 else 8088
+    print(f"Listening on http://localhost:{port}/", flush=True)
+    HTTPServer(("", port), Receiver).serve_forever()